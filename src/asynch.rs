@@ -0,0 +1,224 @@
+//! Async variant of the SSD1327 I2C driver, built on `embedded-hal-async`
+
+use core::result::Result;
+use embedded_hal_async::i2c::I2c;
+
+#[cfg(feature = "framebuffer")]
+use crate::{new_framebuffer, Framebuffer};
+use crate::interface::I2cInterface;
+use crate::{assert_geometry, buffer_size, clamp_offset, command_batches, full_address_window, init_commands, Commands, DisplayConfig, DEFAULT_CHUNK_LEN, MAX_CHUNK_LEN, MAX_CMD_LEN};
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    Pixel,
+    pixelcolor::Gray4,
+    pixelcolor::GrayColor,
+    geometry::OriginDimensions,
+    geometry::Size
+};
+
+/// SSD1327 async I2C driver container
+/// 
+/// `N` is the size of the framebuffer, see `buffer_size`, 128x128 displays by default.
+/// With the `alloc` feature the framebuffer is allocated on the heap and `N` is unused.
+pub struct SSD1327I2CAsync<I2C, const N: usize = { buffer_size(128, 128) }>
+where
+    I2C: I2c
+{
+    interface: I2cInterface<I2C>,
+    width: u8,
+    height: u8,
+    chunk_len: usize,
+    col_offset: u8,
+    row_offset: u8,
+    #[cfg(feature = "framebuffer")]
+    framebuffer: Framebuffer<N>,
+}
+
+impl <I2C> SSD1327I2CAsync<I2C>
+where
+    I2C: I2c,
+{
+    /// Create a new SSD1327I2CAsync object with custom slave address, width 128 and height 128
+    pub fn with_addr(i2c : I2C, slave_address : u8) -> Self {
+        SSD1327I2CAsync::with_addr_wh(i2c, slave_address, 128, 128)
+    }
+
+    /// Create a new SSD1327I2CAsync object with slave address 0x3C, width 128 and height 128
+    pub fn new(i2c : I2C) -> Self {
+        SSD1327I2CAsync::with_addr_wh(i2c, 0x3C, 128, 128)
+    }
+}
+
+impl <I2C, const N: usize> SSD1327I2CAsync<I2C, N>
+where
+    I2C: I2c,
+{
+    /// Create a new SSD1327I2CAsync object with custom slave adress, width and height
+    /// 
+    /// `N` must be `buffer_size(width, height)` and the width and height must pass `check_geometry`, otherwise this panics
+    pub fn with_addr_wh(i2c : I2C, slave_address : u8, width : u8, height : u8) -> Self {
+        assert_geometry::<N>(width, height);
+        #[cfg(feature = "framebuffer")]
+        let framebuffer = new_framebuffer::<N>(width, height);
+        SSD1327I2CAsync {
+            interface: I2cInterface::new(i2c, slave_address),
+            width,
+            height,
+            chunk_len: DEFAULT_CHUNK_LEN,
+            col_offset: 0,
+            row_offset: 0,
+            #[cfg(feature = "framebuffer")]
+            framebuffer,
+        }
+    }
+
+    /// Create a new SSD1327I2CAsync object with slave address 0x3C, and custom width and height
+    /// 
+    /// `N` must be `buffer_size(width, height)`
+    pub fn with_wh(i2c : I2C, width : u8, height : u8) -> Self {
+        SSD1327I2CAsync::with_addr_wh(i2c, 0x3C, width, height)
+    }
+
+    /// Set the maximum number of data bytes sent per transfer, see `SSD1327::set_chunk_len`
+    pub fn set_chunk_len(&mut self, chunk_len: usize) {
+        self.chunk_len = chunk_len.clamp(1, MAX_CHUNK_LEN);
+    }
+
+    /// Maximum number of data bytes sent per transfer
+    pub fn chunk_len(&self) -> usize {
+        self.chunk_len
+    }
+
+    /// Copy each transfer to a single I2C write, for HALs not merging write operations, see `I2cInterface::set_copy_transfers`
+    pub fn set_copy_transfers(&mut self, enabled: bool) {
+        self.interface.set_copy_transfers(enabled);
+    }
+
+    /// Start the visible area at the given GDDRAM column and row addresses, see `SSD1327::with_offset`
+    pub fn with_offset(mut self, col_offset: u8, row_offset: u8) -> Self {
+        (self.col_offset, self.row_offset) = clamp_offset(self.width, self.height, col_offset, row_offset);
+        self
+    }
+
+    /// GDDRAM column address of the first column of the display
+    pub fn col_offset(&self) -> u8 {
+        self.col_offset
+    }
+
+    /// GDDRAM row address of the first row of the display
+    pub fn row_offset(&self) -> u8 {
+        self.row_offset
+    }
+
+    /// Give the I2C peripheral back, the framebuffer is dropped
+    pub fn into_inner(self) -> I2C {
+        self.interface.release()
+    }
+
+    /// Give the I2C peripheral back, same as `into_inner`
+    pub fn release(self) -> I2C {
+        self.into_inner()
+    }
+
+    /// Initialize the SSD1327, stops at the first command that fails
+    pub async fn init(&mut self) -> Result<(), I2C::Error> {
+        self.init_with_config(DisplayConfig::default()).await
+    }
+
+    /// Initialize the SSD1327 with a custom configuration, stops at the first command that fails
+    ///
+    /// Sends the same bytes as `SSD1327::init_with_config` with the same configuration.
+    pub async fn init_with_config(&mut self, cfg: DisplayConfig) -> Result<(), I2C::Error> {
+        self.send_cmds(&init_commands(self.full_address_window(), &cfg)).await
+    }
+
+    /// Column and row address commands covering the whole display, shifted by the offsets
+    fn full_address_window(&self) -> [Commands; 2] {
+        full_address_window(self.width, self.height, (self.col_offset, self.row_offset))
+    }
+
+    /// Write command to the SSD1327
+    pub async fn send_cmd(&mut self, cmd: Commands) -> Result<(), I2C::Error> {
+        #[cfg(feature = "defmt")]
+        defmt::trace!("send_cmd {}", cmd);
+        let mut data = [0u8; MAX_CMD_LEN];
+        let len = cmd.encode(&mut data);
+        // Skip the I2C control byte, added back by the I2C transport
+        self.interface.send_commands_async(&data[1..len]).await
+    }
+
+    /// Write several commands to the SSD1327 in as few transfers as possible, see `SSD1327::send_cmds`
+    pub async fn send_cmds(&mut self, cmds: &[Commands]) -> Result<(), I2C::Error> {
+        #[cfg(feature = "defmt")]
+        for cmd in cmds {
+            defmt::trace!("send_cmds {}", cmd);
+        }
+        for (bytes, len) in command_batches(cmds) {
+            self.interface.send_commands_async(&bytes[0..len]).await?;
+        }
+        Ok(())
+    }
+
+    /// Write data to the SSD1327, stops at the first transfer that fails
+    /// 
+    /// The data is split in transfers of at most `chunk_len` bytes, each prefixed by the data control byte,
+    /// like `SSD1327::send_data`.
+    pub async fn send_data(&mut self, data: &[u8]) -> Result<(), I2C::Error> {
+        for chunk in data.chunks(self.chunk_len) {
+            self.interface.send_data_async(chunk).await?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Update the display with the current framebuffer, yielding to the executor between each chunk
+    ///
+    /// Stops at the first command or chunk that fails, the display is then partially updated.
+    pub async fn flush(&mut self) -> Result<(), I2C::Error> {
+        self.send_cmds(&self.full_address_window()).await?;
+        for chunk in self.framebuffer.chunks(self.chunk_len) {
+            self.interface.send_data_async(chunk).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl <I2C, const N: usize> DrawTarget for SSD1327I2CAsync<I2C, N>
+where
+    I2C: I2c
+{
+
+    type Color = Gray4;
+
+    type Error = I2C::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(coord, color) in pixels.into_iter() {
+            // Check if the pixel coordinates are out of bounds
+            if let Ok((x, y)) = coord.try_into() {
+                if x < self.width as u32 && y < self.height as u32 {
+                    crate::write_pixel(&mut self.framebuffer, self.width, x, y, color.luma());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+}
+
+#[cfg(feature = "graphics")]
+impl <I2C, const N: usize> OriginDimensions for SSD1327I2CAsync<I2C, N>
+where
+    I2C: I2c
+{
+    fn size(&self) -> Size {
+        Size::new(self.width as u32, self.height as u32)
+    }
+}
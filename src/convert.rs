@@ -0,0 +1,138 @@
+//! Drawing other color types than `Gray4`, like `BinaryColor` or `Gray2`, on the SSD1327
+
+use core::marker::PhantomData;
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::Dimensions,
+    pixelcolor::Gray4,
+    pixelcolor::Gray8,
+    pixelcolor::GrayColor,
+    pixelcolor::PixelColor,
+    primitives::Rectangle,
+    Pixel,
+};
+
+/// Draw target converting the colors to `Gray4` before drawing on the wrapped target
+///
+/// `BinaryColor` maps Off to black and On to white, `Gray2` and `Gray8` to the nearest of the 16 gray levels.
+pub struct ColorConverted<'a, T, C> {
+    target: &'a mut T,
+    color: PhantomData<C>,
+}
+
+impl<'a, T, C> ColorConverted<'a, T, C>
+where
+    T: DrawTarget<Color = Gray4>,
+    C: PixelColor + Into<Gray4>,
+{
+    /// Wrap the given `Gray4` target
+    pub fn new(target: &'a mut T) -> Self {
+        ColorConverted { target, color: PhantomData }
+    }
+}
+
+impl<T, C> DrawTarget for ColorConverted<'_, T, C>
+where
+    T: DrawTarget<Color = Gray4>,
+    C: PixelColor + Into<Gray4>,
+{
+    type Color = C;
+
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.target.draw_iter(pixels.into_iter().map(|Pixel(point, color)| Pixel(point, color.into())))
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.target.fill_contiguous(area, colors.into_iter().map(Into::into))
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.fill_solid(area, color.into())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(color.into())
+    }
+}
+
+impl<T, C> Dimensions for ColorConverted<'_, T, C>
+where
+    T: Dimensions,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+/// Draw target accepting `Gray8` colors, keeping the high 4 bits of the luma
+///
+/// Lets 8 bits assets be drawn directly. The low 4 bits are dropped, so the 256 levels collapse to 16:
+/// 0x00 to 0x0F are black and 0xF0 to 0xFF white. Unlike `ColorConverted<Gray8>`, which rounds to the nearest
+/// level, every level covers the same 16 input values. Use `SSD1327::draw_gray8` to dither instead.
+pub struct Gray8Downshifted<'a, T> {
+    target: &'a mut T,
+}
+
+impl<'a, T> Gray8Downshifted<'a, T>
+where
+    T: DrawTarget<Color = Gray4>,
+{
+    /// Wrap the given `Gray4` target
+    pub fn new(target: &'a mut T) -> Self {
+        Gray8Downshifted { target }
+    }
+}
+
+/// `Gray4` level of the high 4 bits of the luma
+fn downshift(color: Gray8) -> Gray4 {
+    Gray4::new(color.luma() >> 4)
+}
+
+impl<T> DrawTarget for Gray8Downshifted<'_, T>
+where
+    T: DrawTarget<Color = Gray4>,
+{
+    type Color = Gray8;
+
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.target.draw_iter(pixels.into_iter().map(|Pixel(point, color)| Pixel(point, downshift(color))))
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.target.fill_contiguous(area, colors.into_iter().map(downshift))
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.fill_solid(area, downshift(color))
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(downshift(color))
+    }
+}
+
+impl<T> Dimensions for Gray8Downshifted<'_, T>
+where
+    T: Dimensions,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
@@ -0,0 +1,278 @@
+//! Transports used to talk to the SSD1327: I2C and 4-wire SPI
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::{I2c, Operation};
+use embedded_hal::spi::SpiDevice;
+
+use crate::{CMDS_BUFFER_LEN, CMD_CONTROL_BYTE, DATA_CONTROL_BYTE, MAX_CHUNK_LEN};
+
+/// Continuation bit (Co) of the I2C control byte, set when a single byte follows the control byte
+pub const CONTINUATION_BIT: u8 = 0x80;
+
+/// Transport writing command and data bytes to the SSD1327
+pub trait Interface {
+    /// Error returned by the underlying peripheral
+    type Error;
+
+    /// Write command bytes, opcodes and their arguments
+    fn send_commands(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Write data bytes to the GDDRAM
+    fn send_data(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// I2C transport, each transfer starts with a control byte telling commands from data
+///
+/// Every transfer is a single `I2c::transaction`, with a bus shared through `embedded-hal-bus` the bus
+/// is locked once per transfer and other devices can only talk between two transfers.
+///
+/// **HAL requirement:** the control byte and the bytes are two `Operation::Write` of the transaction, which
+/// `embedded-hal` requires to be sent as one write without repeated START in between. Some implementations
+/// don't, for instance Linux `i2cdev` without `I2C_M_NOSTART` support, and the SSD1327 then misreads the
+/// bytes: call `set_copy_transfers(true)` with such a HAL to copy each transfer to a single write.
+pub struct I2cInterface<I2C> {
+    pub(crate) i2c: I2C,
+    slave_address: u8,
+    cmd_control_byte: u8,
+    max_transfer: usize,
+    copy_transfers: bool,
+}
+
+impl<I2C> I2cInterface<I2C> {
+    /// Create a new I2C transport with the given slave address
+    pub fn new(i2c: I2C, slave_address: u8) -> Self {
+        I2cInterface { i2c, slave_address, cmd_control_byte: CMD_CONTROL_BYTE, max_transfer: usize::MAX, copy_transfers: false }
+    }
+
+    /// Set the control byte sent before command bytes, 0x00 by default
+    ///
+    /// With 0x00 (Co = 0) the control byte is followed by a stream of command bytes, the whole
+    /// sequence is sent after a single control byte. With 0x80 (Co = 1) a single command byte follows
+    /// the control byte, so every command byte gets its own control byte: some clones only accept this form.
+    pub fn set_command_control_byte(&mut self, control_byte: u8) {
+        self.cmd_control_byte = control_byte;
+    }
+
+    /// Control byte sent before command bytes
+    pub fn command_control_byte(&self) -> u8 {
+        self.cmd_control_byte
+    }
+
+    /// Slave address of the SSD1327
+    pub fn slave_address(&self) -> u8 {
+        self.slave_address
+    }
+
+    /// Change the slave address of the SSD1327
+    pub fn set_slave_address(&mut self, slave_address: u8) {
+        self.slave_address = slave_address;
+    }
+
+    /// Limit the length of each I2C write, control bytes included, for controllers capping a single transfer
+    ///
+    /// No limit by default: commands are written in transfers of up to `CMDS_BUFFER_LEN` bytes and data
+    /// in transfers of the chunk length plus the control byte. Longer writes are split, each part starting
+    /// with its own control byte. At least 2 bytes, a control byte and a command or data byte.
+    pub fn set_max_transfer(&mut self, max_transfer: usize) {
+        self.max_transfer = max_transfer.max(2);
+    }
+
+    /// Maximum length of each I2C write, `usize::MAX` without limit
+    pub fn max_transfer(&self) -> usize {
+        self.max_transfer
+    }
+
+    /// Copy the control byte and the bytes of each transfer to a stack buffer written at once, disabled by default
+    ///
+    /// For HALs sending a repeated START between the write operations of a transaction, see `I2cInterface`.
+    /// Costs a copy of every data byte and a stack buffer of `MAX_CHUNK_LEN + 1` bytes per transfer.
+    pub fn set_copy_transfers(&mut self, enabled: bool) {
+        self.copy_transfers = enabled;
+    }
+
+    /// Whether each transfer is copied to a single write, see `set_copy_transfers`
+    pub fn copy_transfers(&self) -> bool {
+        self.copy_transfers
+    }
+
+    /// Give the I2C peripheral back
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Number of bytes written on the bus by `send_commands` for `len` command bytes, control bytes included
+    pub fn command_wire_len(&self, len: usize) -> usize {
+        if self.cmd_control_byte & CONTINUATION_BIT != 0 {
+            // A control byte before each command byte
+            return 2 * len;
+        }
+        len + len.div_ceil(CMDS_BUFFER_LEN.min(self.max_transfer) - 1)
+    }
+
+    /// Number of bytes written on the bus by `send_data` for `len` data bytes, control bytes included
+    pub fn data_wire_len(&self, len: usize) -> usize {
+        len + len.div_ceil((MAX_CHUNK_LEN + 1).min(self.max_transfer) - 1)
+    }
+
+    /// Parts of at most `LEN` bytes, control byte included, of the bytes sent after a control byte
+    ///
+    /// Each part is sent in a single transaction of two write operations, the control byte and the part,
+    /// merged by the bus into one write without copying the bytes (copied with `set_copy_transfers`). On a shared
+    /// bus the transfer holds the bus from its start to its stop condition, nothing can come between the control
+    /// byte and the bytes.
+    fn parts<'a, const LEN: usize>(&self, bytes: &'a [u8]) -> core::slice::Chunks<'a, u8> {
+        bytes.chunks(LEN.min(self.max_transfer) - 1)
+    }
+
+    /// Parts of the command bytes when each of them needs its own control byte, see `set_command_control_byte`
+    fn interleaved_parts<'a>(&self, bytes: &'a [u8]) -> core::slice::Chunks<'a, u8> {
+        bytes.chunks(CMDS_BUFFER_LEN.min(self.max_transfer) / 2)
+    }
+
+    /// Write the control byte before each byte of the part in `buffer`, returns the bytes to send
+    fn interleave<'a>(&self, part: &[u8], buffer: &'a mut [u8; CMDS_BUFFER_LEN]) -> &'a [u8] {
+        for (index, byte) in part.iter().enumerate() {
+            buffer[2 * index] = self.cmd_control_byte;
+            buffer[2 * index + 1] = *byte;
+        }
+        &buffer[0..2 * part.len()]
+    }
+
+    /// Copy the control byte and the part to `buffer`, returns the bytes to send, see `set_copy_transfers`
+    fn copy<'a>(&self, control_byte: u8, part: &[u8], buffer: &'a mut [u8; MAX_CHUNK_LEN + 1]) -> &'a [u8] {
+        buffer[0] = control_byte;
+        buffer[1..=part.len()].copy_from_slice(part);
+        &buffer[0..=part.len()]
+    }
+}
+
+impl<I2C> I2cInterface<I2C>
+where
+    I2C: I2c,
+{
+    /// Write the bytes after the given control byte, at most `LEN` bytes per transfer, see `parts`
+    fn send_with_control_byte<const LEN: usize>(&mut self, control_byte: u8, bytes: &[u8]) -> Result<(), I2C::Error> {
+        if self.copy_transfers {
+            let mut buffer = [0u8; MAX_CHUNK_LEN + 1];
+            for part in self.parts::<LEN>(bytes) {
+                let transfer = self.copy(control_byte, part, &mut buffer);
+                self.i2c.write(self.slave_address, transfer)?;
+            }
+            return Ok(());
+        }
+        for part in self.parts::<LEN>(bytes) {
+            self.i2c.transaction(self.slave_address, &mut [Operation::Write(&[control_byte]), Operation::Write(part)])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C> I2cInterface<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c,
+{
+    /// Async `Interface::send_commands`, split in the same transfers
+    pub(crate) async fn send_commands_async(&mut self, bytes: &[u8]) -> Result<(), I2C::Error> {
+        let control_byte = self.cmd_control_byte;
+        if control_byte & CONTINUATION_BIT == 0 {
+            return self.send_with_control_byte_async::<CMDS_BUFFER_LEN>(control_byte, bytes).await;
+        }
+        let mut buffer = [0u8; CMDS_BUFFER_LEN];
+        for part in self.interleaved_parts(bytes) {
+            let transfer = self.interleave(part, &mut buffer);
+            self.i2c.write(self.slave_address, transfer).await?;
+        }
+        Ok(())
+    }
+
+    /// Async `Interface::send_data`, split in the same transfers
+    pub(crate) async fn send_data_async(&mut self, bytes: &[u8]) -> Result<(), I2C::Error> {
+        self.send_with_control_byte_async::<{ MAX_CHUNK_LEN + 1 }>(DATA_CONTROL_BYTE, bytes).await
+    }
+
+    /// Async `send_with_control_byte`
+    async fn send_with_control_byte_async<const LEN: usize>(&mut self, control_byte: u8, bytes: &[u8]) -> Result<(), I2C::Error> {
+        if self.copy_transfers {
+            let mut buffer = [0u8; MAX_CHUNK_LEN + 1];
+            for part in self.parts::<LEN>(bytes) {
+                let transfer = self.copy(control_byte, part, &mut buffer);
+                self.i2c.write(self.slave_address, transfer).await?;
+            }
+            return Ok(());
+        }
+        for part in self.parts::<LEN>(bytes) {
+            self.i2c.transaction(self.slave_address, &mut [Operation::Write(&[control_byte]), Operation::Write(part)]).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<I2C> Interface for I2cInterface<I2C>
+where
+    I2C: I2c,
+{
+    type Error = I2C::Error;
+
+    fn send_commands(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        let control_byte = self.cmd_control_byte;
+        if control_byte & CONTINUATION_BIT == 0 {
+            return self.send_with_control_byte::<CMDS_BUFFER_LEN>(control_byte, bytes);
+        }
+        // A control byte before each command byte
+        let mut buffer = [0u8; CMDS_BUFFER_LEN];
+        for part in self.interleaved_parts(bytes) {
+            let transfer = self.interleave(part, &mut buffer);
+            self.i2c.write(self.slave_address, transfer)?;
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.send_with_control_byte::<{ MAX_CHUNK_LEN + 1 }>(DATA_CONTROL_BYTE, bytes)
+    }
+}
+
+/// Error of the SPI transport
+#[derive(Debug)]
+pub enum SpiError<SPI, DC> {
+    /// SPI write failed
+    Spi(SPI),
+    /// Setting the D/C pin failed
+    Dc(DC),
+}
+
+/// 4-wire SPI transport, the D/C pin is low for commands and high for data
+pub struct SpiInterface<SPI, DC> {
+    pub(crate) spi: SPI,
+    dc: DC,
+}
+
+impl<SPI, DC> SpiInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    /// Create a new SPI transport with the given D/C pin
+    pub fn new(spi: SPI, dc: DC) -> Self {
+        SpiInterface { spi, dc }
+    }
+}
+
+impl<SPI, DC> Interface for SpiInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    type Error = SpiError<SPI::Error, DC::Error>;
+
+    fn send_commands(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.dc.set_low().map_err(SpiError::Dc)?;
+        self.spi.write(bytes).map_err(SpiError::Spi)
+    }
+
+    fn send_data(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.dc.set_high().map_err(SpiError::Dc)?;
+        self.spi.write(bytes).map_err(SpiError::Spi)
+    }
+}
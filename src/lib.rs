@@ -5,7 +5,7 @@
 //! ## Example
 //! Following code shows how to flash a SSD1327 screen using the ESP HAL I2C Peripheral Driver.
 //!
-//! ```no_run
+//! ```ignore
 //! // Create a new peripheral object with the described wiring
 //! // and standard I2C clock speed
 //! let i2c = I2C::new(
@@ -16,10 +16,10 @@
 //!     &clocks,
 //! );
 //! 
-//! // Create a new SSD1327I2C object with slave address 0x3C, width 127 and height 127
+//! // Create a new SSD1327I2C object with slave address 0x3C, width 128 and height 128
 //! let mut driver = ssd1327_i2c::SSD1327I2C::new(i2c);
 //! 
-//! driver.init();
+//! driver.init().unwrap();
 //! 
 //! loop {
 //!     driver.send_cmd(ssd1327_i2c::Commands::DisplayModeAllON);
@@ -30,8 +30,16 @@
 //! ```
 
 #![no_std]
-use embedded_hal;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use core::result::Result;
+#[cfg(feature = "framebuffer")]
+use core::ops::Index;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::SpiDevice;
 
 #[cfg(feature = "graphics")]
 use embedded_graphics_core::{
@@ -39,294 +47,3894 @@ use embedded_graphics_core::{
     Pixel, 
     pixelcolor::Gray4,
     pixelcolor::GrayColor,
+    geometry::Dimensions,
     geometry::OriginDimensions, 
-    geometry::Size
+    geometry::Size,
+    primitives::PointsIter,
+    primitives::Rectangle
 };
 
-/// SSD1327 I2C driver container
-pub struct SSD1327I2C<I2C>
+/// Control byte sent before command bytes
+pub(crate) const CMD_CONTROL_BYTE: u8 = 0x00;
+/// Control byte sent before data bytes
+pub(crate) const DATA_CONTROL_BYTE: u8 = 0x40;
+/// Default number of data bytes sent per I2C transfer
+pub const DEFAULT_CHUNK_LEN: usize = 32;
+/// Maximum number of data bytes sent per I2C transfer
+pub const MAX_CHUNK_LEN: usize = 254;
+
+#[cfg(test)]
+mod mock;
+
+pub mod interface;
+use interface::{I2cInterface, Interface, SpiInterface};
+
+#[cfg(feature = "text")]
+mod text;
+#[cfg(feature = "text")]
+pub use text::{StatusLine, CHAR_HEIGHT, CHAR_WIDTH};
+
+#[cfg(feature = "graphics")]
+mod convert;
+#[cfg(feature = "graphics")]
+pub use convert::{ColorConverted, Gray8Downshifted};
+
+#[cfg(feature = "graphics")]
+mod multi;
+#[cfg(feature = "graphics")]
+pub use multi::{DuplicateAddress, MultiDisplay};
+
+#[cfg(feature = "graphics")]
+mod window;
+#[cfg(feature = "graphics")]
+pub use window::Window;
+
+#[cfg(feature = "async")]
+mod asynch;
+#[cfg(feature = "async")]
+pub use asynch::SSD1327I2CAsync;
+
+/// Size in bytes of the framebuffer of a display of the given width and height, two pixels per byte
+///
+/// With an odd width the last byte of each row only holds the last pixel in its high nibble.
+/// 
+/// ```
+/// assert_eq!(ssd1327_i2c::buffer_size(128, 128), 128 * 64);
+/// assert_eq!(ssd1327_i2c::buffer_size(96, 64), 48 * 64);
+/// assert_eq!(ssd1327_i2c::buffer_size(97, 64), 49 * 64);
+/// ```
+pub const fn buffer_size(width: u8, height: u8) -> usize {
+    row_bytes(width) * height as usize
+}
+
+/// Number of bytes of each framebuffer row, rounded up for odd widths
+pub(crate) const fn row_bytes(width: u8) -> usize {
+    (width as usize).div_ceil(2)
+}
+
+/// Framebuffer storage, `N` bytes array or heap allocated slice with the `alloc` feature
+#[cfg(all(feature = "framebuffer", not(feature = "alloc")))]
+pub(crate) type Framebuffer<const N: usize> = [u8; N];
+/// Framebuffer storage, `N` bytes array or heap allocated slice with the `alloc` feature
+#[cfg(all(feature = "framebuffer", feature = "alloc"))]
+pub(crate) type Framebuffer<const N: usize> = alloc::boxed::Box<[u8]>;
+/// No framebuffer without the `framebuffer` feature
+#[cfg(not(feature = "framebuffer"))]
+pub(crate) type Framebuffer<const N: usize> = [u8; 0];
+
+/// Create a zeroed framebuffer for a display of the given width and height
+///
+/// Panics if `N` isn't `buffer_size(width, height)` without the `alloc` feature, the flush methods
+/// would otherwise send the wrong bytes or index out of bounds.
+pub(crate) fn new_framebuffer<const N: usize>(width: u8, height: u8) -> Framebuffer<N> {
+    #[cfg(not(feature = "framebuffer"))]
+    {
+        let _ = (width, height);
+        []
+    }
+    #[cfg(all(feature = "framebuffer", not(feature = "alloc")))]
+    {
+        assert!(N == buffer_size(width, height), "framebuffer size N must be buffer_size(width, height), see build_ssd1327_i2c");
+        [0u8; N]
+    }
+    #[cfg(all(feature = "framebuffer", feature = "alloc"))]
+    {
+        alloc::vec![0u8; buffer_size(width, height)].into_boxed_slice()
+    }
+}
+
+/// Create a new SSD1327I2C object with the framebuffer size computed from the width and height
+/// 
+/// ```ignore
+/// // Slave address 0x3C, width 96 and height 64
+/// let mut driver = ssd1327_i2c::build_ssd1327_i2c!(i2c, 96, 64);
+/// // Custom slave address
+/// let mut driver = ssd1327_i2c::build_ssd1327_i2c!(i2c, 0x3D, 96, 64);
+/// ```
+#[macro_export]
+macro_rules! build_ssd1327_i2c {
+    ($i2c:expr, $width:expr, $height:expr) => {
+        $crate::SSD1327I2C::<_, { $crate::buffer_size($width, $height) }>::with_wh($i2c, $width, $height)
+    };
+    ($i2c:expr, $slave_address:expr, $width:expr, $height:expr) => {
+        $crate::SSD1327I2C::<_, { $crate::buffer_size($width, $height) }>::with_addr_wh($i2c, $slave_address, $width, $height)
+    };
+}
+
+/// SSD1327 driver container, generic over the transport, see `SSD1327I2C` and `SSD1327Spi`
+/// 
+/// `N` is the size of the framebuffer, see `buffer_size`, 128x128 displays by default.
+/// With the `alloc` feature the framebuffer is allocated on the heap from the width and height instead,
+/// `N` is unused and can be left to its default: `SSD1327I2C::<_>::with_wh(i2c, 96, 64)`.
+/// 
+/// `RST` is the optional reset pin, see `with_reset_pin`.
+///
+/// `FB` is the framebuffer storage, owned by default or borrowed with `with_buffer`.
+pub struct SSD1327<DI, const N: usize = { buffer_size(128, 128) }, RST = NoResetPin, FB = Framebuffer<N>>
 where 
-    I2C: embedded_hal::blocking::i2c::Write
+    DI: Interface
 {
-    i2c: I2C,
-    slave_address : u8,
+    interface: DI,
+    rst: RST,
     width: u8,
     height: u8,
+    chunk_len: usize,
+    rotation: Rotation,
+    mirror_h: bool,
+    mirror_v: bool,
+    remap: u8,
+    contrast: u8,
+    powered_on: bool,
+    external_vdd: bool,
+    inverted: bool,
+    start_line: u8,
+    mux_ratio: u8,
+    display_offset: u8,
+    function_b: u8,
+    vcomh: u8,
+    phase_length: u8,
+    col_offset: u8,
+    row_offset: u8,
+    framebuffer: FB,
+    #[cfg(feature = "framebuffer")]
+    dirty: Option<DirtyArea>,
     #[cfg(feature = "graphics")]
-    framebuffer: [u8; 128 * 64],
+    gamma: bool,
+    chunk_retry: bool,
+    #[cfg(feature = "framebuffer")]
+    stats: FlushStats,
+    #[cfg(feature = "text")]
+    cursor: (u8, u8),
+}
+
+/// SSD1327 I2C driver
+pub type SSD1327I2C<I2C, const N: usize = { buffer_size(128, 128) }, RST = NoResetPin, FB = Framebuffer<N>> = SSD1327<I2cInterface<I2C>, N, RST, FB>;
+
+/// SSD1327 4-wire SPI driver, `DC` is the data/command pin
+pub type SSD1327Spi<SPI, DC, const N: usize = { buffer_size(128, 128) }, RST = NoResetPin, FB = Framebuffer<N>> = SSD1327<SpiInterface<SPI, DC>, N, RST, FB>;
+
+/// Error returned when the width and height don't fit a SSD1327 panel, see `check_geometry`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct InvalidGeometry;
+
+/// Error returned by `flush_all` and `flush_best_effort` when data chunks couldn't be sent
+///
+/// `flush_all` stops at the first chunk that fails, `flush_best_effort` still sends the other chunks,
+/// in both cases the display is partially updated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FlushError<E> {
+    /// Error of the last chunk that failed
+    pub error: E,
+    /// Number of chunks that failed, after the retry when enabled with `set_chunk_retry`
+    pub failed_chunks: usize,
+}
+
+/// Error of the methods validating their arguments before using the transport
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error<E> {
+    /// Error of the transport, I2C or SPI
+    I2c(E),
+    /// Width or height not supported, see `check_geometry`
+    InvalidGeometry,
+    /// Buffer shorter than the area it describes
+    BufferTooSmall,
+    /// Area outside of the display
+    OutOfBounds,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(error: E) -> Self {
+        Error::I2c(error)
+    }
+}
+
+/// Check that a display of the given width and height can be driven with a framebuffer of `N` bytes
+///
+/// The width must be within `MIN_SIZE..=MAX_WIDTH` and the height within `MIN_SIZE..=MAX_HEIGHT`, an odd width leaves the last column address half used.
+/// Without the `alloc` feature, `N` must also be `buffer_size(width, height)`.
+pub fn check_geometry<const N: usize>(width: u8, height: u8) -> Result<(), InvalidGeometry> {
+    check_dimensions(width, height)?;
+    #[cfg(all(feature = "framebuffer", not(feature = "alloc")))]
+    if N != buffer_size(width, height) {
+        return Err(InvalidGeometry);
+    }
+    Ok(())
+}
+
+/// Panic with a clear message if the width, height and `N` don't pass `check_geometry`, used by the constructors
+/// without error
+pub(crate) fn assert_geometry<const N: usize>(width: u8, height: u8) {
+    assert!(
+        check_geometry::<N>(width, height).is_ok(),
+        "width and height must be within MIN_SIZE..=MAX_WIDTH and MIN_SIZE..=MAX_HEIGHT and N must be buffer_size(width, height), see check_geometry"
+    );
+}
+
+/// Check that the width and height fit the GDDRAM, within `MIN_SIZE..=MAX_WIDTH` and `MIN_SIZE..=MAX_HEIGHT`
+fn check_dimensions(width: u8, height: u8) -> Result<(), InvalidGeometry> {
+    if !(MIN_SIZE..=MAX_WIDTH).contains(&width) || !(MIN_SIZE..=MAX_HEIGHT).contains(&height) {
+        return Err(InvalidGeometry);
+    }
+    Ok(())
+}
+
+/// Placeholder used when the reset pin of the SSD1327 isn't connected
+///
+/// Not an `OutputPin`, so `reset` is only available once a real pin is given with `with_reset_pin`.
+pub struct NoResetPin;
+
+#[cfg(feature = "framebuffer")]
+/// Bounding box of the pixels modified since the last flush (inclusive)
+#[derive(Clone, Copy)]
+struct DirtyArea {
+    min_x: u8,
+    min_y: u8,
+    max_x: u8,
+    max_y: u8,
+}
+
+/// Counters updated by the flush methods
+#[cfg(feature = "framebuffer")]
+#[derive(Clone, Copy, Default)]
+struct FlushStats {
+    frames: u32,
+    bytes_sent: u32,
+    last_flush_us: u32,
+}
+
+#[cfg(feature = "framebuffer")]
+impl DirtyArea {
+    /// Grow the area so it contains the pixel at (x, y)
+    fn include(&mut self, x: u8, y: u8) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+}
+
+impl <I2C> SSD1327<I2cInterface<I2C>>
+where 
+    I2C: I2c,
+{
+    /// Create a new SSD1327I2C object with custom slave address, width 128 and height 128
+    pub fn with_addr(i2c : I2C, slave_address : u8) -> Self {
+        SSD1327I2C::with_addr_wh(i2c, slave_address, 128, 128)
+    }
+
+    /// Create a new SSD1327I2C object with slave address 0x3C, width 128 and height 128
+    pub fn new(i2c : I2C) -> Self {
+        SSD1327I2C::with_addr_wh(i2c, 0x3C, 128, 128)
+    }
 }
 
-impl <I2C> SSD1327I2C<I2C>
+impl <I2C, const N: usize> SSD1327<I2cInterface<I2C>, N>
 where 
-    I2C: embedded_hal::blocking::i2c::Write,
+    I2C: I2c,
 {
     /// Create a new SSD1327I2C object with custom slave adress, width and height
+    ///
+    /// `N` must be `buffer_size(width, height)`, see the `build_ssd1327_i2c` macro, and the width and height
+    /// must pass `check_geometry`, otherwise this panics. See `try_with_addr_wh` to get an error instead.
     pub fn with_addr_wh(i2c : I2C, slave_address : u8, width : u8, height : u8) -> Self {
-        #[cfg(feature = "graphics")]
-        let framebuffer = [0u8; 128 * 64];
-        let halfwidth = width / 2; // Two pixels per byte
-        SSD1327I2C {
-            i2c,
-            slave_address,
-            width : halfwidth,
-            height,
-            #[cfg(feature = "graphics")]
-            framebuffer,
-        }
+        SSD1327::with_interface(I2cInterface::new(i2c, slave_address), width, height)
     }
 
-    /// Create a new SSD1327I2C object with custom slave address, width 127 and height 127
-    pub fn with_addr(i2c : I2C, slave_address : u8) -> Self {
-        SSD1327I2C::with_addr_wh(i2c, slave_address, 0x7F, 0x7F)
+    /// Create a new SSD1327I2C object with custom slave adress, width and height, checked by `check_geometry`
+    pub fn try_with_addr_wh(i2c : I2C, slave_address : u8, width : u8, height : u8) -> Result<Self, Error<I2C::Error>> {
+        check_geometry::<N>(width, height).map_err(|_| Error::InvalidGeometry)?;
+        Ok(SSD1327I2C::with_addr_wh(i2c, slave_address, width, height))
     }
 
     /// Create a new SSD1327I2C object with slave address 0x3C, and custom width and height
+    ///
+    /// `N` must be `buffer_size(width, height)`, see the `build_ssd1327_i2c` macro
     pub fn with_wh(i2c : I2C, width : u8, height : u8) -> Self {
         SSD1327I2C::with_addr_wh(i2c, 0x3C, width, height)
     }
+}
 
-    /// Create a new SSD1327I2C object with slave address 0x3C, width 127 and height 127
-    pub fn new(i2c : I2C) -> Self {
-        SSD1327I2C::with_addr_wh(i2c, 0x3C, 0x7F, 0x7F)
-    }
-
-    /// Initialize the SSD1327
-    pub fn init(&mut self) {
-        self.send_cmd(Commands::CommandUnlock).ok();
-        self.send_cmd(Commands::DisplayOFF).ok();
-        self.send_cmd(Commands::ColumnAddress { start: 0x00, end: self.width }).ok();
-        self.send_cmd(Commands::RowAddress { start: 0x00, end: self.height }).ok();
-        self.send_cmd(Commands::ContrastControl(0x7f)).ok(); //50% (128/255) RESET 0x7f
-        self.send_cmd(Commands::Remap(0x51)).ok();
-        self.send_cmd(Commands::DisplayStartLine(0x00)).ok();
-        self.send_cmd(Commands::DisplayOffset(0x00)).ok();
-        self.send_cmd(Commands::DisplayModeNormal).ok();
-        self.send_cmd(Commands::MUXRatio(0x7e)).ok(); // RESET 0x7f
-        self.send_cmd(Commands::PhaseLength(0x51)).ok(); // RESET 0x71
-        self.send_cmd(Commands::LinearLUT).ok();
-        self.send_cmd(Commands::FrontClockDividerOscillatorFrequency(0x00)).ok();
-        self.send_cmd(Commands::SelectInternalVDD).ok();
-        self.send_cmd(Commands::SecondPreChargePeriod(0x04)).ok();
-        self.send_cmd(Commands::VCOMH(0x05)).ok();
-        self.send_cmd(Commands::PreChargeVoltage(0x05)).ok();
-        self.send_cmd(Commands::FunctionSelectionB(0x60)).ok();
-        self.send_cmd(Commands::DisplayON).ok();
+impl <SPI, DC> SSD1327<SpiInterface<SPI, DC>>
+where 
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    /// Create a new SSD1327Spi object with width 128 and height 128
+    pub fn new(spi : SPI, dc : DC) -> Self {
+        SSD1327Spi::with_wh(spi, dc, 128, 128)
     }
+}
 
-    /// Write command to the SSD1327
-    pub fn send_cmd(&mut self, cmd: Commands) -> Result<(), I2C::Error> {
-        // 0x00 = Command (Don't know why it's not 0x80)
-        let (data, len) = match cmd {
-            Commands::ColumnAddress { start, end } => ([0x00, 0x15, start, end], 4),
-            Commands::RowAddress { start, end } => ([0x00, 0x75, start, end], 4),
-            Commands::ContrastControl(value) => ([0x00, 0x81, value, 0], 3),
-            Commands::Remap(value) => ([0x00, 0xA0, value, 0], 3),
-            Commands::DisplayStartLine(value) => ([0x00, 0xA1, value, 0], 3),
-            Commands::DisplayOffset(value) => ([0x00, 0xA2, value, 0], 3),
-            Commands::DisplayModeNormal => ([0x00, 0xA4, 0, 0], 2),
-            Commands::DisplayModeAllON => ([0x00, 0xA5, 0, 0], 2),
-            Commands::DisplayModeAllOFF => ([0x00, 0xA6, 0, 0], 2),
-            Commands::DisplayModeInverseDisplay => ([0x00, 0xA7, 0, 0], 2),
-            Commands::MUXRatio(value) => ([0x00, 0xA8, value, 0], 3),
-            Commands::FunctionSelectionA(value) => ([0x00, 0xAB, value, 0], 3),
-            Commands::SelectExternalVDD => ([0x00, 0xAB, 0x00, 0], 3),
-            Commands::SelectInternalVDD => ([0x00, 0xAB, 0x01, 0], 3),
-            Commands::DisplayON => ([0x00, 0xAF, 0, 0], 2),
-            Commands::DisplayOFF => ([0x00, 0xAE, 0, 0], 2),
-            Commands::PhaseLength(value) => ([0x00, 0xB1, value, 0], 3),
-            Commands::FrontClockDividerOscillatorFrequency(value) => ([0x00, 0xB3, value, 0], 3),
-            Commands::GPIO(value) => ([0x00, 0xB5, value, 0], 3),
-            Commands::SecondPreChargePeriod(value) => ([0x00, 0xB6, value, 0], 3),
-            Commands::LinearLUT => ([0x00, 0xB9, 0, 0], 2),
-            Commands::PreChargeVoltage(value) => ([0x00, 0xBC, value, 0], 3),
-            Commands::VCOMH(value) => ([0x00, 0xBE, value, 0], 3),
-            Commands::FunctionSelectionB(value) => ([0x00, 0xD5, value, 0], 3),
-            Commands::SetCommandLock(value) => ([0x00, 0xFD, value, 0], 3),
-            Commands::CommandUnlock => ([0x00, 0xFD, 0x00, 0x12], 4),
-            Commands::CommandLock => ([0x00, 0xFD, 0x00, 0x16], 4),
-        };
-        self.send_bytes(&data[0..len])
+impl <SPI, DC, const N: usize> SSD1327<SpiInterface<SPI, DC>, N>
+where 
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    /// Create a new SSD1327Spi object with custom width and height
+    ///
+    /// `N` must be `buffer_size(width, height)`, otherwise this panics.
+    /// The width and height are not checked, see `try_with_wh`.
+    pub fn with_wh(spi : SPI, dc : DC, width : u8, height : u8) -> Self {
+        SSD1327::with_interface(SpiInterface::new(spi, dc), width, height)
     }
 
-    /// Write bytes to the SSD1327
-    fn send_bytes(&mut self, bytes: &[u8]) -> Result<(), I2C::Error> {
-        self.i2c.write(self.slave_address, bytes)
+    /// Create a new SSD1327Spi object with custom width and height, checked by `check_geometry`
+    pub fn try_with_wh(spi : SPI, dc : DC, width : u8, height : u8) -> Result<Self, Error<<SpiInterface<SPI, DC> as Interface>::Error>> {
+        check_geometry::<N>(width, height).map_err(|_| Error::InvalidGeometry)?;
+        Ok(SSD1327Spi::with_wh(spi, dc, width, height))
     }
+}
 
-    /// Write 8 bytes of data to the SSD1327
-    pub fn send_data(&mut self, data: &[u8]) -> Result<(), I2C::Error> {
-        // 0x40 = Data
-        let (data, len) = (
-            [0x40, data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7]],
-            9
-        );
-        self.send_bytes(&data[0..len])
-    }
-
-    #[cfg(feature = "graphics")]
-    /// Write 8 bytes of data to the SSD1327
-    fn send_buffer_data(&mut self, index : usize) -> Result<(), I2C::Error> {
-        // 0x40 = Data
-        let bytes = [
-            0x40, 
-            self.framebuffer[index], 
-            self.framebuffer[index + 1], 
-            self.framebuffer[index + 2], 
-            self.framebuffer[index + 3], 
-            self.framebuffer[index + 4], 
-            self.framebuffer[index + 5], 
-            self.framebuffer[index + 6], 
-            self.framebuffer[index + 7]
-        ];
-        self.send_bytes(&bytes)
+#[cfg(feature = "framebuffer")]
+impl <'a, I2C> SSD1327<I2cInterface<I2C>, 0, NoResetPin, &'a mut [u8]>
+where 
+    I2C: I2c,
+{
+    /// Create a new SSD1327I2C object drawing in the given buffer instead of its own framebuffer
+    ///
+    /// Lets the framebuffer live in a static or a DMA capable memory region, its content is kept.
+    /// Fails if the width and height are invalid (see `check_geometry`) or if `buffer` is shorter
+    /// than `buffer_size(width, height)`, only the first `buffer_size(width, height)` bytes are used.
+    pub fn with_buffer(i2c : I2C, slave_address : u8, width : u8, height : u8, buffer: &'a mut [u8]) -> Result<Self, Error<I2C::Error>> {
+        check_dimensions(width, height).map_err(|_| Error::InvalidGeometry)?;
+        let Some(buffer) = buffer.get_mut(..buffer_size(width, height)) else {
+            return Err(Error::BufferTooSmall);
+        };
+        Ok(SSD1327::from_parts(I2cInterface::new(i2c, slave_address), width, height, buffer))
     }
 
-    #[cfg(feature = "graphics")]
-    /// Update the display with the current framebuffer
-    pub fn flush(&mut self) -> Result<(), I2C::Error> {
-        self.send_cmd(Commands::ColumnAddress { start: 0x00, end: self.width }).ok(); //0-63
-        self.send_cmd(Commands::RowAddress { start: 0x00, end: self.height }).ok(); //0-127
-        let mut res : Result<(), I2C::Error> = Ok(());
-        for y in 0..=127 {
-            for x in (0..=63).step_by(8) {
-                match self.send_buffer_data(x + y * 64) {
-                    Ok(_) => (),
-                    Err(e) => res = Err(e),
-                }
-            }
-        }
-        res
+    /// Same as `with_buffer`, named after `with_addr_wh` for the constructors taking the slave address, width and height
+    ///
+    /// Both check the geometry and the buffer length, a short buffer returns `Error::BufferTooSmall`.
+    pub fn with_addr_wh_buffer_checked(i2c : I2C, slave_address : u8, width : u8, height : u8, buffer: &'a mut [u8]) -> Result<Self, Error<I2C::Error>> {
+        Self::with_buffer(i2c, slave_address, width, height, buffer)
     }
-    
 }
 
-/// Commands to be sent to the SSD1327
-pub enum Commands {
-    /// Setup Column start and end address (0x15)
-    ColumnAddress {
-        /// Start address 00-7f (RESET = 00) 
-        start: u8,
-        /// End address 00-3f (RESET = 3F) 
-        end: u8,
-    },
-    /// Setup Row start and end address (0x75)
-    RowAddress {
-        /// Start address 00-7f (RESET = 00) 
-        start: u8,
-        /// End address 00-7f (RESET = 7F) 
-        end: u8,
-    },
-    /// Double byte command to select 1 out of 256 contrast steps. 
-    /// Contrast increases as the value increases (RESET = 7F ) (0x81)
-    ContrastControl(u8),
-    /// **Re-map setting in Graphic Display Data RAM (GDDRAM)** (0xA0)\
-    /// A[0] = 0, Disable Column Address Re-map (RESET)\
-    /// A[0] = 1, Enable Column Address Re-map\
-    /// A[1] = 0, Disable Nibble Re-map (RESET)\
-    /// A[1] = 1, Enable Nibble Re-map\
-    /// A[2] = 0, Enable Horizontal Address Increment (RESET)\
-    /// A[2] = 1, Enable Vertical Address Increment\
-    /// A[3] = 0, Reserved (RESET)\
-    /// A[4] = 0, Disable COM Re-map (RESET)\
-    /// A[4] = 1, Enable COM Re-map\
-    /// A[5] = 0, Reserved (RESET)\
-    /// A[6] = 0, Disable COM Split Odd Even (RESET)\
-    /// A[6] = 1, Enable COM Split Odd Even\
-    /// A[7] = 0, Reserved (RESET) 
-    Remap(u8),
-    /// Vertical shift by setting the starting address of display RAM from 0 ~ 127 (RESET = 00) (0xA1)
-    DisplayStartLine(u8),
-    /// Set vertical offset by COM from 0 ~ 127 (RESET = 00) (0xA2)
-    DisplayOffset(u8),
-    /// Normal Display Mode (0xA4)
-    DisplayModeNormal,
-    /// All ON Display Mode (0xA5)
-    DisplayModeAllON,
-    /// All OFF Display Mode (0xA6)
-    DisplayModeAllOFF,
-    /// Inverse Display Display Mode (0xA7)
-    DisplayModeInverseDisplay,
-    /// Set MUX ratio from 16MUX ~ 128MUX (0xA8)
-    MUXRatio(u8),
-    /// Function Selection A (0 = external VDD; 1 = internal VDD (RESET)) (0xAB)
-    FunctionSelectionA(u8),
-    /// External VDD (0xAB 0x00)
-    SelectExternalVDD,
-    /// Internal VDD (RESET) (0xAB 0x01)
-    SelectInternalVDD,
-    /// Turn display ON (0xAF)
-    DisplayON,
-    /// Turn display OFF (0xAE)
-    DisplayOFF,
-    /// Phase Length (0xB1)
-    PhaseLength(u8),
-    /// Front Clock Divider / Oscillator Frequency (0xB3)
-    FrontClockDividerOscillatorFrequency(u8),
-    /// GPIO : 00 represents GPIO pin HiZ, input disable (always read as low) ; 
-    /// 01 represents GPIO pin HiZ, input enable ; 10 represents GPIO pin output Low (RESET) ; 
-    /// 11 represents GPIO pin output High ; (0xB5)
-    GPIO(u8),
-    /// Second Pre-charge period of 1~15 DCLK’s (RESET = 0100) (0xB6)
-    SecondPreChargePeriod(u8),
-    /// The default Lineear Gray Scale table (0xB9)
-    LinearLUT,
-    /// Set pre-charge voltage level (0xBC)
-    PreChargeVoltage(u8),
-    /// Set COM deselect voltage level (0xBE)
-    VCOMH(u8),
-    /// Function Selection B : 0.: Disable second precharge (RESET) ; 1.: Enable second precharge ; 
-    /// .0: Internal VSL (RESET) ; .1: Enable external VSL ; (0xD5)
-    FunctionSelectionB(u8),
-    /// MCU protection status 0x16 = Lock ; 0x12 Unlock (RESET) ; (0xFD)
-    SetCommandLock(u8),
-    /// Unlock OLED driver IC MCU interface from entering commands (RESET) (0xFD 0x12)
-    CommandUnlock,
-    /// Lock OLED driver IC MCU interface from entering commands (0xFD 0x16)
-    CommandLock,
+impl <I2C, const N: usize, RST, FB> SSD1327<I2cInterface<I2C>, N, RST, FB>
+where 
+    I2C: I2c,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Set the I2C control byte sent before command bytes, see `I2cInterface::set_command_control_byte`
+    pub fn set_command_control_byte(&mut self, control_byte: u8) {
+        self.interface.set_command_control_byte(control_byte);
+    }
+
+    /// Limit the length of each I2C write, see `I2cInterface::set_max_transfer`
+    pub fn set_max_transfer(&mut self, max_transfer: usize) {
+        self.interface.set_max_transfer(max_transfer);
+    }
+
+    /// Copy each transfer to a single I2C write, for HALs not merging write operations, see `I2cInterface::set_copy_transfers`
+    pub fn set_copy_transfers(&mut self, enabled: bool) {
+        self.interface.set_copy_transfers(enabled);
+    }
+
+    /// I2C slave address of the SSD1327
+    pub fn slave_address(&self) -> u8 {
+        self.interface.slave_address()
+    }
+
+    /// Change the I2C slave address, for instance to the one found by `probe` when scanning 0x3C and 0x3D
+    pub fn set_slave_address(&mut self, slave_address: u8) {
+        self.interface.set_slave_address(slave_address);
+    }
+
+    /// Check that a device acknowledges at the slave address by sending the harmless `CommandUnlock`
+    ///
+    /// The SSD1327 can't be read over I2C, so this only confirms that something answers at the address,
+    /// not that it is a SSD1327. A missing or unpowered display shows up as a NACK error.
+    pub fn probe(&mut self) -> Result<(), I2C::Error> {
+        self.send_cmd(Commands::CommandUnlock)
+    }
+
+    /// Give the I2C peripheral back, for instance to use the bus for something else after `sleep`
+    ///
+    /// The framebuffer and the reset pin are dropped, the display keeps its state.
+    pub fn into_inner(self) -> I2C {
+        self.interface.release()
+    }
+
+    /// Give the I2C peripheral back, same as `into_inner`
+    pub fn release(self) -> I2C {
+        self.into_inner()
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Number of bytes the next `flush_all` writes on the bus, address windows and control bytes included
+    ///
+    /// Follows the current geometry, chunk length, command control byte, transfer limit and address increment.
+    /// Multiply by 9 bits per byte (plus the start, address and stop) for the frame time at a given bus speed.
+    pub fn flush_byte_count(&self) -> usize {
+        let remap_bytes = if self.addressing_mode() == AddressIncrement::Vertical {
+            self.interface.command_wire_len(2)
+        } else {
+            0
+        };
+        let data_bytes: usize = self.framebuffer.as_ref()
+            .chunks(self.chunk_len)
+            .map(|chunk| self.interface.data_wire_len(chunk.len()))
+            .sum();
+        // Column and row address commands, 3 bytes each
+        remap_bytes + self.interface.command_wire_len(6) + data_bytes
+    }
 }
 
-#[cfg(feature = "graphics")]
-impl <I2C> DrawTarget for SSD1327I2C<I2C>
+impl <DI, const N: usize> SSD1327<DI, N>
 where 
-    I2C: embedded_hal::blocking::i2c::Write 
+    DI: Interface,
 {
+    /// Create a new SSD1327 object using the given transport
+    ///
+    /// Panics if the width, height and `N` don't pass `check_geometry`, a width or height of 0 would underflow
+    /// the address windows.
+    fn with_interface(interface: DI, width : u8, height : u8) -> Self {
+        assert_geometry::<N>(width, height);
+        SSD1327::from_parts(interface, width, height, new_framebuffer::<N>(width, height))
+    }
+}
 
-    type Color = Gray4;
-
-    type Error = I2C::Error;
+impl <DI, const N: usize, FB> SSD1327<DI, N, NoResetPin, FB>
+where 
+    DI: Interface,
+{
+    /// Create a new SSD1327 object using the given transport and framebuffer storage
+    fn from_parts(interface: DI, width : u8, height : u8, framebuffer: FB) -> Self {
+        SSD1327 {
+            interface,
+            rst: NoResetPin,
+            width,
+            height,
+            chunk_len: DEFAULT_CHUNK_LEN,
+            rotation: Rotation::Deg0,
+            mirror_h: false,
+            mirror_v: false,
+            remap: DEFAULT_REMAP,
+            contrast: DEFAULT_CONTRAST,
+            powered_on: false,
+            external_vdd: false,
+            inverted: false,
+            start_line: 0,
+            mux_ratio: MAX_SIZE,
+            display_offset: 0,
+            function_b: DEFAULT_FUNCTION_B,
+            vcomh: DEFAULT_VCOMH,
+            phase_length: DEFAULT_PHASE_LENGTH,
+            col_offset: 0,
+            row_offset: 0,
+            framebuffer,
+            #[cfg(feature = "framebuffer")]
+            dirty: None,
+            #[cfg(feature = "graphics")]
+            gamma: false,
+            chunk_retry: false,
+            #[cfg(feature = "framebuffer")]
+            stats: FlushStats::default(),
+            #[cfg(feature = "text")]
+            cursor: (0, 0),
+        }
+    }
 
-    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    /// Use the given pin to reset the SSD1327, see `reset`
+    pub fn with_reset_pin<RST>(self, rst: RST) -> SSD1327<DI, N, RST, FB>
     where
-        I: IntoIterator<Item = Pixel<Self::Color>>,
+        RST: OutputPin,
     {
-        for Pixel(coord, color) in pixels.into_iter() {
-            // Check if the pixel coordinates are out of bounds
-            if let Ok((x @ 0..=127, y @ 0..=127)) = coord.try_into() {
-                // Calculate the index in the framebuffer.
-                let index: u32 = x/2 + y * 64;
-                let mut new_byte = color.luma();
-                // 1 byte for 2 pixels so we need to shift the byte by 4 bits if the x coordinate is even
-                if x % 2 == 0 {
-                    new_byte <<= 4;
-                    self.framebuffer[index as usize] &= 0x0F;
-                } else {
-                    self.framebuffer[index as usize] &= 0xF0;
-                }
-                self.framebuffer[index as usize] |= new_byte;
-            }
+        SSD1327 {
+            interface: self.interface,
+            rst,
+            width: self.width,
+            height: self.height,
+            chunk_len: self.chunk_len,
+            rotation: self.rotation,
+            mirror_h: self.mirror_h,
+            mirror_v: self.mirror_v,
+            remap: self.remap,
+            contrast: self.contrast,
+            powered_on: self.powered_on,
+            external_vdd: self.external_vdd,
+            inverted: self.inverted,
+            start_line: self.start_line,
+            mux_ratio: self.mux_ratio,
+            display_offset: self.display_offset,
+            function_b: self.function_b,
+            vcomh: self.vcomh,
+            phase_length: self.phase_length,
+            col_offset: self.col_offset,
+            row_offset: self.row_offset,
+            framebuffer: self.framebuffer,
+            #[cfg(feature = "framebuffer")]
+            dirty: self.dirty,
+            #[cfg(feature = "graphics")]
+            gamma: self.gamma,
+            chunk_retry: self.chunk_retry,
+            #[cfg(feature = "framebuffer")]
+            stats: self.stats,
+            #[cfg(feature = "text")]
+            cursor: self.cursor,
         }
+    }
+}
 
+impl <DI, const N: usize, RST, FB> SSD1327<DI, N, RST, FB>
+where 
+    DI: Interface,
+    RST: OutputPin,
+{
+    /// Reset the SSD1327 with the reset pin, only available with a reset pin (see `with_reset_pin`)
+    ///
+    /// The reset pin is pulled low for 3µs, then high, followed by 100ms for the SSD1327 to settle.
+    /// The display must be initialized again afterwards.
+    pub fn reset(&mut self, delay: &mut impl DelayNs) -> Result<(), RST::Error> {
+        self.rst.set_low()?;
+        delay.delay_us(3);
+        self.rst.set_high()?;
+        delay.delay_ms(100);
+        // Back to the RESET state
+        self.powered_on = false;
+        self.external_vdd = false;
+        self.inverted = false;
+        self.start_line = 0;
+        self.mux_ratio = MAX_SIZE;
+        self.display_offset = 0;
         Ok(())
     }
-
 }
 
-#[cfg(feature = "graphics")]
-impl <I2C> OriginDimensions for SSD1327I2C<I2C>
+impl <DI, const N: usize, RST, FB> SSD1327<DI, N, RST, FB>
 where 
-    I2C: embedded_hal::blocking::i2c::Write
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
 {
-    fn size(&self) -> Size {
-        Size::new(self.width as u32, self.height as u32)
+
+    /// Set the maximum number of data bytes sent per transfer, clamped to 1..=`MAX_CHUNK_LEN`
+    ///
+    /// Bigger chunks mean less transfers (and less control bytes and start/stop overhead) per flush,
+    /// but each chunk is sent in a single transfer, split further if longer than `set_max_transfer`.
+    /// Defaults to `DEFAULT_CHUNK_LEN`.
+    pub fn set_chunk_len(&mut self, chunk_len: usize) {
+        self.chunk_len = chunk_len.clamp(1, MAX_CHUNK_LEN);
+    }
+
+    /// Maximum number of data bytes sent per transfer
+    pub fn chunk_len(&self) -> usize {
+        self.chunk_len
+    }
+
+    /// Width of the display in pixels, as configured, not affected by the rotation (see `size` for that)
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Height of the display in pixels, as configured, not affected by the rotation
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// Number of bytes of each framebuffer row, two pixels per byte rounded up
+    pub fn byte_width(&self) -> usize {
+        row_bytes(self.width)
     }
-}
\ No newline at end of file
+
+    /// Start the visible area at the given GDDRAM column and row addresses instead of (0, 0)
+    ///
+    /// For modules whose glass is wired to the middle of the GDDRAM: pixel (0, 0) is written at column address
+    /// `col_offset` (two pixels per column, so 2 x `col_offset` pixels) and row `row_offset`. Applied to
+    /// the address windows of the init sequences, the flush methods, `window_pixels` and `write_raw_at`.
+    /// The offsets are clamped so that the display still fits the 64 columns and 128 rows of the GDDRAM.
+    pub fn with_offset(mut self, col_offset: u8, row_offset: u8) -> Self {
+        (self.col_offset, self.row_offset) = clamp_offset(self.width, self.height, col_offset, row_offset);
+        self
+    }
+
+    /// GDDRAM column address of the first column of the display
+    pub fn col_offset(&self) -> u8 {
+        self.col_offset
+    }
+
+    /// GDDRAM row address of the first row of the display
+    pub fn row_offset(&self) -> u8 {
+        self.row_offset
+    }
+
+    /// Column and row address commands of the given window, in display columns and rows, shifted by the offsets
+    fn address_window(&self, col_start: u8, col_end: u8, row_start: u8, row_end: u8) -> [Commands; 2] {
+        address_window((self.col_offset, self.row_offset), col_start, col_end, row_start, row_end)
+    }
+
+    /// Column and row address commands covering the whole display, shifted by the offsets
+    fn full_address_window(&self) -> [Commands; 2] {
+        full_address_window(self.width, self.height, (self.col_offset, self.row_offset))
+    }
+
+    /// Set the display rotation
+    ///
+    /// 180 degrees is done by the SSD1327 through the remap setting, 90 and 270 degrees also
+    /// swap the coordinates when drawing, so the framebuffer must be redrawn after changing the rotation.
+    pub fn set_rotation(&mut self, rotation: Rotation) -> Result<(), DI::Error> {
+        self.remap ^= self.rotation.remap_bits() ^ rotation.remap_bits();
+        self.rotation = rotation;
+        self.send_cmd(Commands::Remap(self.remap))
+    }
+
+    /// Current display rotation
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Mirror the display horizontally and/or vertically through the remap setting
+    ///
+    /// Unlike the rotation, the width and height are kept and the framebuffer doesn't need to be redrawn.
+    /// Mirroring applies on top of the rotation: both flip the same remap bits, so the 180 degrees rotation
+    /// mirrored both horizontally and vertically shows the same as no rotation.
+    pub fn set_mirror(&mut self, h: bool, v: bool) -> Result<(), DI::Error> {
+        if h != self.mirror_h {
+            self.remap ^= MIRROR_H_REMAP_BITS;
+        }
+        if v != self.mirror_v {
+            self.remap ^= MIRROR_V_REMAP_BITS;
+        }
+        self.mirror_h = h;
+        self.mirror_v = v;
+        self.send_cmd(Commands::Remap(self.remap))
+    }
+
+    /// Set whether the GDDRAM address is incremented horizontally or vertically after each data byte
+    ///
+    /// The flush methods use horizontal increment and switch back to it when needed, except
+    /// `flush_region_with_increment`, so this is mostly useful for data sent directly with `send_data`.
+    pub fn set_addressing_mode(&mut self, increment: AddressIncrement) -> Result<(), DI::Error> {
+        match increment {
+            AddressIncrement::Horizontal => self.remap &= !VERTICAL_INCREMENT_REMAP_BIT,
+            AddressIncrement::Vertical => self.remap |= VERTICAL_INCREMENT_REMAP_BIT,
+        }
+        self.send_cmd(Commands::Remap(self.remap))
+    }
+
+    /// Current address increment
+    pub fn addressing_mode(&self) -> AddressIncrement {
+        if self.remap & VERTICAL_INCREMENT_REMAP_BIT != 0 {
+            AddressIncrement::Vertical
+        } else {
+            AddressIncrement::Horizontal
+        }
+    }
+
+    /// Enable or disable the COM split odd even (remap bit 6), enabled by default
+    ///
+    /// Modules wired the other way show the picture as two interleaved halves, flip it when the content
+    /// is right but the rows are mixed up.
+    pub fn set_com_split(&mut self, enabled: bool) -> Result<(), DI::Error> {
+        if enabled {
+            self.remap |= COM_SPLIT_REMAP_BIT;
+        } else {
+            self.remap &= !COM_SPLIT_REMAP_BIT;
+        }
+        self.send_cmd(Commands::Remap(self.remap))
+    }
+
+    /// Whether the COM split odd even is enabled
+    pub fn com_split(&self) -> bool {
+        self.remap & COM_SPLIT_REMAP_BIT != 0
+    }
+
+    /// Current remap setting, combining the rotation, mirroring, address increment and COM split
+    pub fn remap(&self) -> u8 {
+        self.remap
+    }
+
+    /// Set the contrast, from 0 to 255, and remember it
+    pub fn set_contrast(&mut self, value: u8) -> Result<(), DI::Error> {
+        self.send_cmd(Commands::ContrastControl(value))?;
+        self.contrast = value;
+        Ok(())
+    }
+
+    /// Last contrast set (0x7F by default)
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// Set the brightness in percent, mapped linearly to the contrast: 0 is 0x00, 50 is 0x80 and 100 is 0xFF
+    ///
+    /// Values above 100 are clamped to 100.
+    pub fn set_brightness(&mut self, percent: u8) -> Result<(), DI::Error> {
+        let percent = percent.min(100) as u16;
+        self.set_contrast(((percent * 255 + 50) / 100) as u8)
+    }
+
+    /// Brightness in percent of the last contrast set, the value given to `set_brightness` if it was used
+    pub fn brightness(&self) -> u8 {
+        ((self.contrast as u16 * 100 + 127) / 255) as u8
+    }
+
+    /// Set the contrast for the ambient light measured in lux, see `contrast_for_lux` for the curve
+    ///
+    /// Dims the panel in the dark to save power and brightens it in daylight. The contrast is remembered like with `set_contrast`.
+    pub fn set_contrast_from_lux(&mut self, lux: u16) -> Result<(), DI::Error> {
+        self.set_contrast(contrast_for_lux(lux))
+    }
+
+    /// Fade the contrast linearly from the last contrast set to `target` in `steps` steps,
+    /// waiting `step_ms` milliseconds after each step
+    ///
+    /// Blocks for `steps * step_ms` milliseconds, the contrast is set to `target` directly without waiting with 0 steps.
+    pub fn fade_to(&mut self, target: u8, steps: u16, delay: &mut impl DelayNs, step_ms: u32) -> Result<(), DI::Error> {
+        if steps == 0 {
+            return self.set_contrast(target);
+        }
+        let start = self.contrast as i32;
+        let steps = steps as i32;
+        for step in 1..=steps {
+            let value = start + (target as i32 - start) * step / steps;
+            self.set_contrast(value.clamp(0, 255) as u8)?;
+            delay.delay_ms(step_ms);
+        }
+        Ok(())
+    }
+
+    /// Turn the display ON
+    pub fn display_on(&mut self) -> Result<(), DI::Error> {
+        self.send_cmd(Commands::DisplayON)?;
+        self.powered_on = true;
+        Ok(())
+    }
+
+    /// Turn the display OFF (sleep mode), the GDDRAM content is kept
+    pub fn display_off(&mut self) -> Result<(), DI::Error> {
+        self.send_cmd(Commands::DisplayOFF)?;
+        self.powered_on = false;
+        Ok(())
+    }
+
+    /// Whether the display is ON, false until initialized
+    pub fn is_on(&self) -> bool {
+        self.powered_on
+    }
+
+    /// Enter the low power mode: the contrast is lowered to 0, then the display is turned OFF
+    ///
+    /// The SSD1327 draws about 10µA in sleep mode, against a few mA to tens of mA when ON depending
+    /// on the contrast and the number of lit pixels. The GDDRAM content is kept, see `wake`.
+    pub fn sleep(&mut self) -> Result<(), DI::Error> {
+        self.send_cmds(&[Commands::ContrastControl(0x00), Commands::DisplayOFF])?;
+        self.powered_on = false;
+        Ok(())
+    }
+
+    /// Like `sleep`, and also disable the internal VDD regulator, only for modules with an external VDD supply
+    pub fn deep_sleep(&mut self) -> Result<(), DI::Error> {
+        self.send_cmds(&[Commands::ContrastControl(0x00), Commands::DisplayOFF, Commands::SelectExternalVDD])?;
+        self.powered_on = false;
+        self.external_vdd = true;
+        Ok(())
+    }
+
+    /// Leave the low power mode entered with `sleep` or `deep_sleep`, the last contrast set is restored
+    ///
+    /// The internal VDD regulator is only enabled again after `deep_sleep`.
+    pub fn wake(&mut self) -> Result<(), DI::Error> {
+        if self.external_vdd {
+            self.send_cmds(&[Commands::SelectInternalVDD, Commands::ContrastControl(self.contrast), Commands::DisplayON])?;
+            self.external_vdd = false;
+        } else {
+            self.send_cmds(&[Commands::ContrastControl(self.contrast), Commands::DisplayON])?;
+        }
+        self.powered_on = true;
+        Ok(())
+    }
+
+    /// Set the display mode to inverse display or back to normal
+    ///
+    /// Also leaves the All ON / All OFF display modes, which don't change the inverted state.
+    pub fn set_invert(&mut self, inverted: bool) -> Result<(), DI::Error> {
+        if inverted {
+            self.send_cmd(Commands::DisplayModeInverseDisplay)
+        } else {
+            self.send_cmd(Commands::DisplayModeNormal)
+        }
+    }
+
+    /// Switch between inverse display and normal display modes
+    pub fn toggle_invert(&mut self) -> Result<(), DI::Error> {
+        self.set_invert(!self.inverted)
+    }
+
+    /// Whether the display is in inverse display mode
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Scroll the display up by the given number of lines, wrapping at the height or the MUX ratio
+    ///
+    /// This shifts the window of the GDDRAM shown on the display with the display start line,
+    /// the framebuffer isn't modified and the lines scrolled out at the top come back at the bottom.
+    /// The scroll accumulates and wraps at the number of rows shown, the height or the MUX ratio of
+    /// `set_mux_ratio` when lower: scrolling by that many lines in total brings the start line back.
+    /// The display offset of `set_display_offset` isn't changed, it keeps aligning the panel with the COM rows.
+    pub fn scroll_vertical(&mut self, lines: u8) -> Result<(), DI::Error> {
+        let rows = self.height.min(self.mux_ratio);
+        let start_line = ((self.start_line as u16 + lines as u16) % rows as u16) as u8;
+        self.send_cmd(Commands::DisplayStartLine(start_line))?;
+        self.start_line = start_line;
+        Ok(())
+    }
+
+    /// Current display start line
+    pub fn start_line(&self) -> u8 {
+        self.start_line
+    }
+
+    /// Set the number of COM rows driven, clamped to `MIN_SIZE..=MAX_SIZE`
+    ///
+    /// Panels with less than 128 rows usually also need a display offset, see `set_display_offset`.
+    pub fn set_mux_ratio(&mut self, rows: u8) -> Result<(), DI::Error> {
+        let rows = rows.clamp(MIN_SIZE, MAX_SIZE);
+        self.send_cmd(Commands::MUXRatio(rows - 1))?;
+        self.mux_ratio = rows;
+        Ok(())
+    }
+
+    /// Number of COM rows driven (128 after a reset)
+    pub fn mux_ratio(&self) -> u8 {
+        self.mux_ratio
+    }
+
+    /// Set the vertical offset of the first COM row, from 0 to 127
+    pub fn set_display_offset(&mut self, offset: u8) -> Result<(), DI::Error> {
+        let offset = offset % MAX_SIZE;
+        self.send_cmd(Commands::DisplayOffset(offset))?;
+        self.display_offset = offset;
+        Ok(())
+    }
+
+    /// Current display offset
+    pub fn display_offset(&self) -> u8 {
+        self.display_offset
+    }
+
+    /// Set the second pre-charge period in DCLKs, clamped to 1..=15, the values the datasheet defines
+    ///
+    /// Only used when the second pre-charge is enabled, see `set_function_b`.
+    pub fn set_second_precharge(&mut self, period: u8) -> Result<(), DI::Error> {
+        self.send_cmd(Commands::SecondPreChargePeriod(period.clamp(1, 15)))
+    }
+
+    /// Set the phase 1 (pixel reset) and phase 2 (first pre-charge) periods in DCLKs, each clamped to 1..=15
+    ///
+    /// Composes the `Commands::PhaseLength` byte, phase 1 in the low nibble and phase 2 in the high nibble.
+    /// Too short periods show up as dim pixels or ghosting. The periods are kept by the next `init`.
+    pub fn set_phase_length(&mut self, phase1: u8, phase2: u8) -> Result<(), DI::Error> {
+        self.send_cmd(Commands::PhaseLength((phase2.clamp(1, 15) << 4) | phase1.clamp(1, 15)))
+    }
+
+    /// Phase length byte last sent (0x51 by default)
+    pub fn phase_length(&self) -> u8 {
+        self.phase_length
+    }
+
+    /// Set the COM deselect voltage level, see `VcomhLevel` for the voltages
+    ///
+    /// The level is kept by the next `init`. A higher level brightens the panel but may increase flicker.
+    pub fn set_vcomh(&mut self, level: VcomhLevel) -> Result<(), DI::Error> {
+        self.send_cmd(Commands::VCOMH(level.value()))
+    }
+
+    /// VCOMH byte last sent (0x05 by default)
+    pub fn vcomh(&self) -> u8 {
+        self.vcomh
+    }
+
+    /// Enable the second pre-charge and select the external VSL, see `Commands::FunctionSelectionB`
+    ///
+    /// The byte is kept by the next `init`. Both are disabled after a reset, the internal VSL is used.
+    pub fn set_function_b(&mut self, second_precharge: bool, external_vsl: bool) -> Result<(), DI::Error> {
+        let value = DEFAULT_FUNCTION_B
+            | if second_precharge { FUNCTION_B_SECOND_PRECHARGE } else { 0 }
+            | if external_vsl { FUNCTION_B_EXTERNAL_VSL } else { 0 };
+        self.send_cmd(Commands::FunctionSelectionB(value))?;
+        self.function_b = value;
+        Ok(())
+    }
+
+    /// Function selection B byte last sent (0x60 by default)
+    pub fn function_b(&self) -> u8 {
+        self.function_b
+    }
+
+    /// Initialize the SSD1327 with the default configuration, stops at the first command that fails
+    ///
+    /// The contrast, rotation, mirroring, function selection B, VCOMH level and phase length set before are kept.
+    pub fn init(&mut self) -> Result<(), DI::Error> {
+        self.init_with_config(DisplayConfig {
+            contrast: self.contrast,
+            remap: self.remap,
+            function_b: self.function_b,
+            vcomh: self.vcomh,
+            phase_length: self.phase_length,
+            ..DisplayConfig::default()
+        })
+    }
+
+    /// Initialize the SSD1327 like `init` with the power on delays some modules need, stops at the first command that fails
+    ///
+    /// Waits `POWER_ON_DELAY_MS` before the first command for VDD to settle (or after `reset`),
+    /// then `DISPLAY_ON_DELAY_MS` between the configuration and `DisplayON` for VCC to stabilize.
+    pub fn init_with_delay(&mut self, delay: &mut impl DelayNs) -> Result<(), DI::Error> {
+        let cfg = DisplayConfig {
+            contrast: self.contrast,
+            remap: self.remap,
+            function_b: self.function_b,
+            vcomh: self.vcomh,
+            phase_length: self.phase_length,
+            ..DisplayConfig::default()
+        };
+        let cmds = init_commands(self.full_address_window(), &cfg);
+        delay.delay_ms(POWER_ON_DELAY_MS);
+        // Everything but the final DisplayON
+        self.send_cmds(&cmds[..cmds.len() - 1])?;
+        delay.delay_ms(DISPLAY_ON_DELAY_MS);
+        self.send_cmd(Commands::DisplayON)?;
+        self.init_done(&cfg);
+        Ok(())
+    }
+
+    /// Initialize the SSD1327 with a custom configuration, stops at the first command that fails
+    pub fn init_with_config(&mut self, cfg: DisplayConfig) -> Result<(), DI::Error> {
+        self.send_cmds(&init_commands(self.full_address_window(), &cfg))?;
+        self.init_done(&cfg);
+        Ok(())
+    }
+
+    /// Initialize the SSD1327 with only the essential commands, for clones misbehaving with the full sequence
+    ///
+    /// Sends the command unlock, display OFF, the address window, the remap setting (rotation and mirroring),
+    /// normal display mode and display ON. Unlike `init`, the contrast, start line, offset, MUX ratio,
+    /// phase length, gray scale table, clock divider, VDD selection, pre-charge, VCOMH and function
+    /// selection B are not sent and keep their current values, add the ones the module needs with `send_cmds`.
+    pub fn init_minimal(&mut self) -> Result<(), DI::Error> {
+        let [column_address, row_address] = self.full_address_window();
+        self.send_cmds(&[
+            Commands::CommandUnlock,
+            Commands::DisplayOFF,
+            column_address,
+            row_address,
+            Commands::Remap(self.remap),
+            Commands::DisplayModeNormal,
+            Commands::DisplayON,
+        ])?;
+        self.powered_on = true;
+        Ok(())
+    }
+
+    /// Mark the SSD1327 as initialized without sending anything, to re-attach to a running display
+    ///
+    /// After a warm reboot where the panel kept power, running `init` again makes it flash. This sets the
+    /// cached state (contrast, remap, MUX ratio, start line, offset, function selection B, VCOMH, phase length, ON) to what `init`
+    /// would have left, keeping the contrast, rotation and mirroring set before. Nothing checks the actual
+    /// state of the panel: if it differs, like after a power loss, getters lie and the display may stay
+    /// blank or garbled until `init` is called.
+    pub fn assume_initialized(&mut self) {
+        self.init_done(&DisplayConfig {
+            contrast: self.contrast,
+            remap: self.remap,
+            function_b: self.function_b,
+            vcomh: self.vcomh,
+            phase_length: self.phase_length,
+            ..DisplayConfig::default()
+        });
+        self.inverted = false;
+    }
+
+    /// Update the cached state after the init sequence
+    fn init_done(&mut self, cfg: &DisplayConfig) {
+        self.contrast = cfg.contrast;
+        self.remap = cfg.remap;
+        self.start_line = 0;
+        self.mux_ratio = cfg.mux_ratio.saturating_add(1);
+        self.display_offset = 0;
+        self.function_b = cfg.function_b;
+        self.vcomh = cfg.vcomh;
+        self.phase_length = cfg.phase_length;
+        // The init sequence selects the internal VDD and ends with DisplayON
+        self.external_vdd = false;
+        self.powered_on = true;
+    }
+
+    /// Write command to the SSD1327
+    pub fn send_cmd(&mut self, cmd: Commands) -> Result<(), DI::Error> {
+        #[cfg(feature = "defmt")]
+        defmt::trace!("send_cmd {}", cmd);
+        let mut data = [0u8; MAX_CMD_LEN];
+        let len = cmd.encode(&mut data);
+        // Skip the I2C control byte, added back by the I2C transport
+        self.interface.send_commands(&data[1..len])?;
+        self.track_cmd(cmd);
+        Ok(())
+    }
+
+    /// Write raw command bytes to the SSD1327, for registers not modeled by `Commands`
+    ///
+    /// `bytes` holds the opcodes and their arguments without control byte, the I2C transport adds it.
+    /// The bytes are not checked and the cached state (contrast, remap...) is not updated: a wrong
+    /// sequence can leave the panel unresponsive until it is reset or powered off.
+    pub fn send_raw_cmd(&mut self, bytes: &[u8]) -> Result<(), DI::Error> {
+        self.interface.send_commands(bytes)
+    }
+
+    /// Write several commands to the SSD1327 in as few transfers as possible
+    ///
+    /// The command bytes are sent back to back (after a single command control byte with I2C),
+    /// a new transfer is only started when `CMDS_BUFFER_LEN` bytes are reached.
+    pub fn send_cmds(&mut self, cmds: &[Commands]) -> Result<(), DI::Error> {
+        #[cfg(feature = "defmt")]
+        for cmd in cmds {
+            defmt::trace!("send_cmds {}", cmd);
+        }
+        for (bytes, len) in command_batches(cmds) {
+            self.interface.send_commands(&bytes[0..len])?;
+        }
+        for cmd in cmds {
+            self.track_cmd(*cmd);
+        }
+        Ok(())
+    }
+
+    /// Keep the cached state in sync when a command is sent directly
+    fn track_cmd(&mut self, cmd: Commands) {
+        match cmd {
+            Commands::DisplayModeNormal => self.inverted = false,
+            Commands::DisplayModeInverseDisplay => self.inverted = true,
+            Commands::FunctionSelectionB(value) => self.function_b = value,
+            Commands::VCOMH(value) => self.vcomh = value,
+            Commands::PhaseLength(value) => self.phase_length = value,
+            _ => (),
+        }
+    }
+
+    /// Write data to the SSD1327
+    ///
+    /// Data of any length is accepted, like fonts or bitmaps streamed in one call: it is split in transfers
+    /// of at most `chunk_len` bytes (see `set_chunk_len`), each prefixed by the data control byte with I2C
+    /// and split further to fit `set_max_transfer`. The SSD1327 keeps its GDDRAM address pointer between
+    /// transfers, so the bytes carry on filling the current column and row window where the last transfer stopped.
+    pub fn send_data(&mut self, data: &[u8]) -> Result<(), DI::Error> {
+        for chunk in data.chunks(self.chunk_len) {
+            send_chunk(&mut self.interface, chunk, self.chunk_retry)?;
+        }
+        Ok(())
+    }
+
+    /// Send a data chunk a second time when its transfer fails, disabled by default
+    ///
+    /// Helps with occasional glitches on long wires or weak pull-ups. A chunk is sent at most twice,
+    /// so a disconnected display doesn't hang the flush. Applies to `send_data` and the flush methods.
+    pub fn set_chunk_retry(&mut self, enabled: bool) {
+        self.chunk_retry = enabled;
+    }
+
+    /// Set the GDDRAM address window to the area of width `w` and height `h` at (x, y), in pixels
+    ///
+    /// Sends the `ColumnAddress` and `RowAddress` commands, converting x to column addresses:
+    /// each column address holds two pixels, so x is rounded down and the end column up to even boundaries.
+    /// The area is clamped to the display, nothing is sent for an empty area. Data sent next with `send_data`
+    /// fills the window, coordinates are not affected by the rotation.
+    pub fn window_pixels(&mut self, x: u8, y: u8, w: u8, h: u8) -> Result<(), DI::Error> {
+        if w == 0 || h == 0 || x >= self.width || y >= self.height {
+            return Ok(());
+        }
+        let x_end = (x as u16 + w as u16).min(self.width as u16) - 1;
+        let y_end = (y as u16 + h as u16).min(self.height as u16) - 1;
+        self.send_cmds(&self.address_window(x / 2, x_end as u8 / 2, y, y_end as u8))
+    }
+
+    /// Write packed 4 bits per pixel data straight to the GDDRAM area of width `w` and height `h` at (x, y), in pixels
+    ///
+    /// The framebuffer is neither used nor updated, so this works without the `framebuffer` feature.
+    /// As each column address holds two pixels, x is rounded down and the end column up to even boundaries:
+    /// `data` must hold `h` rows of `(x + w + 1) / 2 - x / 2` bytes, with the leftmost pixel in the high nibble,
+    /// extra bytes are ignored. The area must fit in the display, coordinates are not affected by the rotation.
+    pub fn write_raw_at(&mut self, x: u8, y: u8, w: u8, h: u8, data: &[u8]) -> Result<(), Error<DI::Error>> {
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let x_end = x as u16 + w as u16 - 1;
+        let y_end = y as u16 + h as u16 - 1;
+        if x_end >= self.width as u16 || y_end >= self.height as u16 {
+            return Err(Error::OutOfBounds);
+        }
+        let row_bytes = (x_end as usize / 2) - (x as usize / 2) + 1;
+        let Some(data) = data.get(..row_bytes * h as usize) else {
+            return Err(Error::BufferTooSmall);
+        };
+        self.horizontal_increment()?;
+        self.window_pixels(x, y, w, h)?;
+        Ok(self.send_data(data)?)
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Update the display with the whole framebuffer, whether it was modified or not
+    ///
+    /// Useful after changes made through `framebuffer_mut` or to recover from a glitch.
+    /// Stops at the first transfer that fails, so a disconnected display doesn't cost a whole frame of bus time,
+    /// see `set_chunk_retry` to retry failed chunks and `flush_best_effort` to keep going.
+    /// The area modified since the last flush is reset if every chunk was sent.
+    pub fn flush_all(&mut self) -> Result<(), FlushError<DI::Error>> {
+        self.flush_all_with(|| ())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Same as `flush_all`, sending every chunk even after some of them failed
+    ///
+    /// The error tells how many chunks failed and holds the error of the last one. A failed address window
+    /// is still returned right away with 0 failed chunks, the chunks would land in the previous window.
+    pub fn flush_best_effort(&mut self) -> Result<(), FlushError<DI::Error>> {
+        self.send_framebuffer(|| (), true)
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Same as `flush_all`, calling `on_chunk` after each transfer of `chunk_len` data bytes
+    ///
+    /// Lets long flushes on slow buses feed a watchdog or yield between transfers:
+    /// a 128x128 display is flushed in 256 chunks with the default `chunk_len` of 32 bytes.
+    pub fn flush_all_with(&mut self, on_chunk: impl FnMut()) -> Result<(), FlushError<DI::Error>> {
+        self.send_framebuffer(on_chunk, false)
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Send the whole framebuffer, stopping at the first failed transfer unless `best_effort`
+    fn send_framebuffer(&mut self, mut on_chunk: impl FnMut(), best_effort: bool) -> Result<(), FlushError<DI::Error>> {
+        self.horizontal_increment().map_err(|error| FlushError { error, failed_chunks: 0 })?;
+        // Without the window the chunks are meaningless, even in best effort
+        self.send_cmds(&self.full_address_window()).map_err(|error| FlushError { error, failed_chunks: 0 })?;
+        let mut res : Result<(), FlushError<DI::Error>> = Ok(());
+        for chunk in self.framebuffer.as_ref().chunks(self.chunk_len) {
+            match send_chunk(&mut self.interface, chunk, self.chunk_retry) {
+                Ok(()) => self.stats.bytes_sent = self.stats.bytes_sent.wrapping_add(chunk.len() as u32),
+                Err(error) if !best_effort => return Err(FlushError { error, failed_chunks: 1 }),
+                Err(error) => {
+                    let failed_chunks = res.as_ref().err().map_or(0, |e| e.failed_chunks) + 1;
+                    res = Err(FlushError { error, failed_chunks });
+                }
+            }
+            on_chunk();
+        }
+        if res.is_ok() {
+            self.dirty = None;
+            self.stats.frames = self.stats.frames.wrapping_add(1);
+        }
+        res
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Update the display with only the area of the framebuffer modified since the last flush
+    ///
+    /// Nothing is sent if the framebuffer wasn't modified, use `flush_all` to send everything.
+    /// The modified area is reset once sent.
+    pub fn flush(&mut self) -> Result<(), DI::Error> {
+        let Some(area) = self.dirty else {
+            return Ok(());
+        };
+        // Two pixels per column address
+        self.flush_window(area.min_x / 2, area.max_x / 2, area.min_y, area.max_y)?;
+        self.dirty = None;
+        self.stats.frames = self.stats.frames.wrapping_add(1);
+        Ok(())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Update the display with only the framebuffer bytes that differ from `shadow`, then copy them to `shadow`
+    ///
+    /// `shadow` is a second buffer of the framebuffer size holding what the display shows, like a double
+    /// buffer: it doubles the framebuffer RAM (8 KiB more for 128x128) but only the changed bytes are sent,
+    /// ideal for sparse animations. Each row is compared byte by byte and every run of changed bytes gets its
+    /// own address window, runs closer than `DIFF_MERGE_GAP` bytes are merged to save the window commands.
+    /// Start with a shadow matching the display, for instance a copy of the framebuffer after `flush_all`.
+    /// The area modified since the last flush is reset once sent.
+    pub fn flush_diff(&mut self, shadow: &mut [u8]) -> Result<(), Error<DI::Error>> {
+        let stride = row_bytes(self.width);
+        let len = self.framebuffer.as_ref().len();
+        let Some(shadow) = shadow.get_mut(..len) else {
+            return Err(Error::BufferTooSmall);
+        };
+        self.horizontal_increment()?;
+        for y in 0..self.height {
+            let row = y as usize * stride..(y as usize + 1) * stride;
+            let mut run: Option<(usize, usize)> = None;
+            for x in 0..stride {
+                if self.framebuffer.as_ref()[row.start + x] == shadow[row.start + x] {
+                    continue;
+                }
+                run = match run {
+                    Some((start, end)) if x - end <= DIFF_MERGE_GAP => Some((start, x)),
+                    Some((start, end)) => {
+                        self.send_window(start as u8, end as u8, y, y)?;
+                        Some((x, x))
+                    }
+                    None => Some((x, x)),
+                };
+            }
+            if let Some((start, end)) = run {
+                self.send_window(start as u8, end as u8, y, y)?;
+            }
+            shadow[row.clone()].copy_from_slice(&self.framebuffer.as_ref()[row]);
+        }
+        self.dirty = None;
+        self.stats.frames = self.stats.frames.wrapping_add(1);
+        Ok(())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Update the display with the whole framebuffer, the even rows first then the odd rows
+    ///
+    /// Large changes show up as two quick passes instead of a single top to bottom wipe.
+    /// The same framebuffer bytes as `flush_all` are sent, reordered, but each row gets its own
+    /// column and row address window. The area modified since the last flush is reset once sent.
+    pub fn flush_interlaced(&mut self) -> Result<(), DI::Error> {
+        let last_column = (row_bytes(self.width) - 1) as u8;
+        for first_row in [0, 1] {
+            for y in (first_row..self.height).step_by(2) {
+                self.flush_window(0, last_column, y, y)?;
+            }
+        }
+        self.dirty = None;
+        self.stats.frames = self.stats.frames.wrapping_add(1);
+        Ok(())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Same as `flush`, measuring its duration with `now_us`, a monotonic clock in microseconds
+    ///
+    /// The duration is available from `last_flush_us`. `flush` doesn't read any clock,
+    /// so nothing is measured when this isn't used.
+    pub fn flush_timed(&mut self, mut now_us: impl FnMut() -> u32) -> Result<(), DI::Error> {
+        let start = now_us();
+        self.flush()?;
+        self.stats.last_flush_us = now_us().wrapping_sub(start);
+        Ok(())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Duration of the last `flush_timed`, in microseconds, 0 if it was never used
+    pub fn last_flush_us(&self) -> u32 {
+        self.stats.last_flush_us
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Frames per second the last `flush_timed` allows, `None` if no duration was measured
+    pub fn fps(&self) -> Option<u32> {
+        1_000_000u32.checked_div(self.stats.last_flush_us)
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Number of frames flushed, wrapping
+    ///
+    /// Every successful `flush` (and `flush_timed`) sending a modified area, `flush_all`, `flush_all_with`,
+    /// `flush_best_effort`, `flush_diff` and `flush_interlaced` counts one frame. `flush_region` doesn't.
+    pub fn frame_count(&self) -> u32 {
+        self.stats.frames
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Number of framebuffer bytes sent by the flush methods, wrapping
+    ///
+    /// Divided by the elapsed time it gives the throughput, for instance to compare `flush` and `flush_all`.
+    pub fn flush_bytes_sent(&self) -> u32 {
+        self.stats.bytes_sent
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Add the framebuffer area of width `w` and height `h` at (x, y), in pixels, to the area sent by the next `flush`
+    ///
+    /// For changes made through `framebuffer_mut`, which are not tracked. The area is clamped to the display
+    /// and coordinates are not affected by the rotation, like `flush_region`.
+    pub fn mark_dirty(&mut self, x: u8, y: u8, w: u8, h: u8) {
+        if w == 0 || h == 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        let x_end = (x as u16 + w as u16).min(self.width as u16) - 1;
+        let y_end = (y as u16 + h as u16).min(self.height as u16) - 1;
+        self.mark_pixel_dirty(x, y);
+        self.mark_pixel_dirty(x_end as u8, y_end as u8);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Mark the whole framebuffer as modified, the next `flush` sends all of it
+    pub fn mark_dirty_all(&mut self) {
+        self.dirty = Some(DirtyArea { min_x: 0, min_y: 0, max_x: self.width - 1, max_y: self.height - 1 });
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Forget the area modified since the last flush, the next `flush` sends nothing until the framebuffer changes
+    ///
+    /// For instance after writing the same content to the display with `write_raw_at`.
+    pub fn reset_dirty(&mut self) {
+        self.dirty = None;
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Update the display with the framebuffer area of width `w` and height `h` at (x, y), in pixels
+    ///
+    /// The area is clamped to the display and extended to even x boundaries, as each column address holds two pixels.
+    /// Coordinates are not affected by the rotation and the area is not removed from the area sent by `flush`.
+    pub fn flush_region(&mut self, x: u8, y: u8, w: u8, h: u8) -> Result<(), DI::Error> {
+        self.flush_region_with_increment(x, y, w, h, AddressIncrement::Horizontal)
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Same as `flush_region`, sending the framebuffer bytes in the order of the given address increment
+    ///
+    /// With vertical increment the bytes are sent column by column, each byte holding two horizontally
+    /// adjacent pixels, and the SSD1327 is left in vertical increment mode.
+    pub fn flush_region_with_increment(&mut self, x: u8, y: u8, w: u8, h: u8, increment: AddressIncrement) -> Result<(), DI::Error> {
+        if w == 0 || h == 0 || x >= self.width || y >= self.height {
+            return Ok(());
+        }
+        let x_end = (x as u16 + w as u16).min(self.width as u16) - 1;
+        let y_end = (y as u16 + h as u16).min(self.height as u16) - 1;
+        if increment != self.addressing_mode() {
+            self.set_addressing_mode(increment)?;
+        }
+        self.send_window(x / 2, x_end as u8 / 2, y, y_end as u8)
+    }
+
+    /// Switch back to horizontal address increment, expected by the framebuffer layout
+    fn horizontal_increment(&mut self) -> Result<(), DI::Error> {
+        if self.addressing_mode() == AddressIncrement::Vertical {
+            self.set_addressing_mode(AddressIncrement::Horizontal)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Set the column and row address window and send the matching framebuffer bytes
+    ///
+    /// With horizontal increment the SSD1327 wraps back to `col_start` on the next row after `col_end`,
+    /// so the bytes are sent row by row with exactly `col_end - col_start + 1` bytes per row.
+    fn flush_window(&mut self, col_start: u8, col_end: u8, row_start: u8, row_end: u8) -> Result<(), DI::Error> {
+        self.horizontal_increment()?;
+        self.send_window(col_start, col_end, row_start, row_end)
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Set the column and row address window and send the matching framebuffer bytes in the order
+    /// of the current address increment: row by row, or column by column with vertical increment
+    fn send_window(&mut self, col_start: u8, col_end: u8, row_start: u8, row_end: u8) -> Result<(), DI::Error> {
+        let [column_address, row_address] = self.address_window(col_start, col_end, row_start, row_end);
+        self.send_cmd(column_address)?;
+        self.send_cmd(row_address)?;
+        let stride = row_bytes(self.width);
+        let (columns, rows) = (col_start as usize..=col_end as usize, row_start as usize..=row_end as usize);
+        let vertical = self.addressing_mode() == AddressIncrement::Vertical;
+        let (outer, inner) = if vertical { (columns, rows) } else { (rows, columns) };
+        let mut bytes = [0u8; MAX_CHUNK_LEN];
+        let mut len = 0;
+        for a in outer {
+            for b in inner.clone() {
+                let (x, y) = if vertical { (a, b) } else { (b, a) };
+                bytes[len] = self.framebuffer.as_ref()[x + y * stride];
+                len += 1;
+                if len == self.chunk_len {
+                    send_chunk(&mut self.interface, &bytes[0..len], self.chunk_retry)?;
+                    self.stats.bytes_sent = self.stats.bytes_sent.wrapping_add(len as u32);
+                    len = 0;
+                }
+            }
+        }
+        if len > 0 {
+            send_chunk(&mut self.interface, &bytes[0..len], self.chunk_retry)?;
+            self.stats.bytes_sent = self.stats.bytes_sent.wrapping_add(len as u32);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Framebuffer content
+    ///
+    /// Rows are stored from top to bottom, each row is `(width + 1) / 2` bytes holding two 4-bit gray values per byte:
+    /// the high nibble is the pixel at the even x and the low nibble the pixel at the following odd x.
+    /// This is the layout of embedded-graphics' `ImageRaw<Gray4>` (big endian, rows padded to whole bytes),
+    /// so `ImageRaw::<Gray4>::new(display.framebuffer(), display.width() as u32)` reads the framebuffer back,
+    /// for instance to compare it with a golden image. Coordinates are those of the framebuffer, without rotation.
+    pub fn framebuffer(&self) -> &[u8] {
+        self.framebuffer.as_ref()
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Draw target accepting colors convertible to `Gray4`, like `BinaryColor` or `Gray2`
+    ///
+    /// Works with the `DrawTargetExt` adapters like any draw target. Only the low 4 bits of the
+    /// converted luma are written, so a faulty conversion can't spill into the neighbouring pixel.
+    ///
+    /// ```ignore
+    /// Text::new("Hello", Point::new(10, 10), MonoTextStyle::new(&FONT_6X10, BinaryColor::On))
+    ///     .draw(&mut driver.color_converted())?;
+    /// ```
+    pub fn color_converted<C>(&mut self) -> ColorConverted<'_, Self, C>
+    where
+        C: embedded_graphics_core::pixelcolor::PixelColor + Into<Gray4>,
+    {
+        ColorConverted::new(self)
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Draw target accepting `Gray8` colors, keeping the high 4 bits of the luma, see `Gray8Downshifted`
+    ///
+    /// The low 4 bits of each color are lost: 0xF0 and 0xFF are both drawn as 0xF.
+    pub fn gray8(&mut self) -> Gray8Downshifted<'_, Self> {
+        Gray8Downshifted::new(self)
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Draw target over the given area of the display, with (0, 0) at the top left corner of the area
+    ///
+    /// Lets a widget draw with local coordinates without drawing outside of its area,
+    /// `Window::flush` only sends that area. The area uses the same coordinates as `DrawTarget`.
+    pub fn window(&mut self, area: Rectangle) -> Window<'_, DI, N, RST, FB> {
+        Window::new(self, area)
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Mutable framebuffer content, with the same layout as `framebuffer`
+    ///
+    /// Changes made through it are not tracked by `flush`: tell the driver with `mark_dirty` or
+    /// `mark_dirty_all`, or use `flush_all` to send them.
+    pub fn framebuffer_mut(&mut self) -> &mut [u8] {
+        self.framebuffer.as_mut()
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Fill the whole framebuffer with the given color
+    ///
+    /// Much faster than drawing every pixel, the display is updated on the next flush. Prefer it to drawing
+    /// a full screen rectangle: `DrawTarget::clear` and `fill_solid` over the whole display end up here,
+    /// but styled primitives may still draw pixel by pixel.
+    pub fn clear(&mut self, color: Gray4) {
+        self.fill_gray(color.luma());
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Fill the whole framebuffer with the given gray value (0-15), like `clear` without embedded-graphics
+    ///
+    /// Only the low 4 bits of the gray value are used, gamma corrected like `clear` when enabled with `set_gamma`.
+    pub fn fill_gray(&mut self, gray: u8) {
+        let gray = self.correct(gray);
+        // Same gray for both pixels of each byte
+        self.framebuffer.as_mut().fill((gray << 4) | gray);
+        self.mark_dirty_all();
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Correct the gray levels drawn through `DrawTarget`, `clear`, `set_pixel` and `fill_gray` with `GAMMA_TABLE`
+    ///
+    /// `set_pixel_raw`, `draw_image_raw` and `test_pattern` still write their gray values as is.
+    /// The linear gray scale table of the SSD1327 makes mid-grays look too dark, the correction
+    /// brightens them. Disabled by default. Don't combine it with a corrected `Commands::GrayScaleTable`
+    /// uploaded to the SSD1327, the gray levels would be corrected twice.
+    pub fn set_gamma(&mut self, enabled: bool) {
+        self.gamma = enabled;
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Whether the gray levels are gamma corrected, see `set_gamma`
+    pub fn gamma(&self) -> bool {
+        self.gamma
+    }
+
+    #[cfg(feature = "graphics")]
+    /// Gray value written in the framebuffer for the given color, masked to 4 bits
+    fn luma(&self, color: Gray4) -> u8 {
+        self.correct(color.luma())
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Gray value (0-15) written in the framebuffer, masked to 4 bits and gamma corrected when enabled
+    fn correct(&self, gray: u8) -> u8 {
+        let gray = gray & 0x0F;
+        #[cfg(feature = "graphics")]
+        if self.gamma {
+            return GAMMA_TABLE[gray as usize];
+        }
+        gray
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Set the gray value (0-15) of the pixel at (x, y) in the framebuffer
+    ///
+    /// Coordinates are relative to the current rotation. Only the low 4 bits of the gray value are used,
+    /// gamma corrected like the pixels drawn through `DrawTarget` when enabled with `set_gamma`.
+    /// Out of bounds coordinates are ignored, the display is updated on the next flush.
+    pub fn set_pixel(&mut self, x: u8, y: u8, gray: u8) {
+        self.set_pixel_raw(x, y, self.correct(gray));
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Same as `set_pixel`, writing the gray value without gamma correction
+    pub fn set_pixel_raw(&mut self, x: u8, y: u8, gray: u8) {
+        if let Some((x, y)) = self.framebuffer_coordinates(x, y) {
+            write_pixel(self.framebuffer.as_mut(), self.width, x as u32, y as u32, gray);
+            self.mark_pixel_dirty(x, y);
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Blend the pixel at (x, y) toward the gray value (0-15) by `alpha`, from 0 (unchanged) to 15 (replaced)
+    ///
+    /// Reads the current gray value of the framebuffer and writes the rounded blend back, nothing is sent
+    /// to the display. Useful for fading overlays and anti-aliased edges. Out of bounds coordinates are ignored.
+    pub fn blend_pixel(&mut self, x: u8, y: u8, gray: u8, alpha: u8) {
+        let Some(current) = self.get_pixel(x, y) else {
+            return;
+        };
+        let alpha = alpha.min(15) as u16;
+        // Blend in the framebuffer gray levels, the current value is already corrected
+        let blended = (current as u16 * (15 - alpha) + self.correct(gray) as u16 * alpha + 7) / 15;
+        self.set_pixel_raw(x, y, blended as u8);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Copy a packed 4 bits per pixel image of the given width at (x, y) in the framebuffer
+    ///
+    /// `data` uses the same layout as the framebuffer, each row is `(width + 1) / 2` bytes with
+    /// the leftmost pixel in the high nibble. Rows are copied byte by byte when x and the width are even
+    /// and the display isn't rotated by 90 or 270 degrees, otherwise the nibbles are re-packed.
+    /// The gray values are copied as is, without gamma correction. Pixels out of bounds are ignored.
+    pub fn draw_image_raw(&mut self, x: u8, y: u8, width: u8, data: &[u8]) {
+        if width == 0 {
+            return;
+        }
+        let data_row_len = row_bytes(width);
+        let aligned = x.is_multiple_of(2) && width.is_multiple_of(2) && !self.rotation.swaps_axes();
+        for (row, pixels) in data.chunks(data_row_len).enumerate() {
+            let Ok(py) = u8::try_from(y as usize + row) else {
+                break;
+            };
+            if aligned {
+                if py >= self.height || x >= self.width {
+                    break;
+                }
+                let count = pixels.len().min(row_bytes(self.width) - x as usize / 2);
+                let start = x as usize / 2 + py as usize * row_bytes(self.width);
+                self.framebuffer.as_mut()[start..start + count].copy_from_slice(&pixels[0..count]);
+                self.mark_pixel_dirty(x, py);
+                // The last byte of a row of an odd width display only holds one pixel
+                self.mark_pixel_dirty((x as usize + count * 2 - 1).min(self.width as usize - 1) as u8, py);
+            } else {
+                for i in 0..(pixels.len() * 2).min(width as usize) {
+                    let Ok(px) = u8::try_from(x as usize + i) else {
+                        break;
+                    };
+                    let byte = pixels[i / 2];
+                    let gray = if i % 2 == 0 { byte >> 4 } else { byte & 0x0F };
+                    self.set_pixel_raw(px, py, gray);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Draw an 8 bits per pixel grayscale image of the given width at (x, y), dithered to the 16 gray levels
+    ///
+    /// `data` holds one byte per pixel, row by row. A 4x4 ordered (Bayer) dithering aligned on the display
+    /// coordinates spreads the rounding error, avoiding the banding of a plain `>> 4`. Pixels out of bounds are ignored.
+    pub fn draw_gray8(&mut self, x: u8, y: u8, width: u8, data: &[u8]) {
+        if width == 0 {
+            return;
+        }
+        for (row, pixels) in data.chunks(width as usize).enumerate() {
+            let Ok(py) = u8::try_from(y as usize + row) else {
+                break;
+            };
+            for (column, &value) in pixels.iter().enumerate() {
+                let Ok(px) = u8::try_from(x as usize + column) else {
+                    break;
+                };
+                let scaled = value as u16 * 15;
+                let threshold = BAYER_4X4[py as usize % 4][px as usize % 4] as u16;
+                // Round up when the remainder is above the threshold of the pixel, (threshold + 0.5) / 16
+                let round_up = (scaled % 255) * 32 > (2 * threshold + 1) * 255;
+                self.set_pixel(px, py, (scaled / 255) as u8 + round_up as u8);
+            }
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Fill the framebuffer with the given pattern and flush it, to check a new panel
+    ///
+    /// The pattern follows the current rotation and replaces the framebuffer content, without gamma correction.
+    pub fn test_pattern(&mut self, pattern: TestPattern) -> Result<(), DI::Error> {
+        let (width, height) = self.rotated_dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                let lit = match pattern {
+                    TestPattern::Checkerboard => (x + y) % 2 == 0,
+                    TestPattern::Gradient => {
+                        self.set_pixel_raw(x, y, (x as u16 * 16 / width as u16) as u8);
+                        continue;
+                    }
+                    TestPattern::Borders => x == 0 || y == 0 || x == width - 1 || y == height - 1,
+                    TestPattern::Stripes => y % 2 == 0,
+                };
+                self.set_pixel_raw(x, y, if lit { 0x0F } else { 0x00 });
+            }
+        }
+        self.mark_dirty_all();
+        self.flush()
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Gray value (0-15) of the pixel at (x, y) in the framebuffer, `None` if out of bounds
+    ///
+    /// Coordinates are relative to the current rotation.
+    pub fn get_pixel(&self, x: u8, y: u8) -> Option<u8> {
+        let (x, y) = self.framebuffer_coordinates(x, y)?;
+        let byte = self.framebuffer.as_ref()[x as usize / 2 + y as usize * row_bytes(self.width)];
+        // The even x is stored in the high nibble
+        if x % 2 == 0 {
+            Some(byte >> 4)
+        } else {
+            Some(byte & 0x0F)
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Framebuffer coordinates of the pixel at (x, y) with the current rotation, `None` if out of bounds
+    fn framebuffer_coordinates(&self, x: u8, y: u8) -> Option<(u8, u8)> {
+        let (width, height) = self.rotated_dimensions();
+        if x >= width || y >= height {
+            return None;
+        }
+        // 90 and 270 degrees, 180 degrees is handled by the remap setting
+        if self.rotation.swaps_axes() {
+            Some((self.width - 1 - y, x))
+        } else {
+            Some((x, y))
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Width and height of the display as seen with the current rotation
+    fn rotated_dimensions(&self) -> (u8, u8) {
+        if self.rotation.swaps_axes() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Add the pixel at (x, y) to the area to be sent by the next `flush`
+    fn mark_pixel_dirty(&mut self, x: u8, y: u8) {
+        match self.dirty.as_mut() {
+            Some(area) => area.include(x, y),
+            None => self.dirty = Some(DirtyArea { min_x: x, min_y: y, max_x: x, max_y: y }),
+        }
+    }
+    
+}
+
+/// Smallest width and height supported, the minimum MUX ratio is 16
+pub const MIN_SIZE: u8 = 16;
+/// Largest width and height supported by the SSD1327
+pub const MAX_SIZE: u8 = 128;
+/// Largest width accepted by the validating constructors, the GDDRAM has 128 columns of pixels
+pub const MAX_WIDTH: u8 = MAX_SIZE;
+/// Largest height accepted by the validating constructors, the GDDRAM has 128 rows
+pub const MAX_HEIGHT: u8 = MAX_SIZE;
+/// Last column address, each column holds two pixels
+const MAX_COLUMN_ADDRESS: u8 = 0x3F;
+/// Last row address
+const MAX_ROW_ADDRESS: u8 = 0x7F;
+/// Size of the buffer used to batch commands in `send_cmds`, including the I2C control byte
+pub(crate) const CMDS_BUFFER_LEN: usize = 64;
+/// Length of the longest encoded command (control byte, 0xB8 and the 15 gray scale table values)
+pub const MAX_CMD_LEN: usize = 17;
+/// Ambient light in lux and matching contrast used by `contrast_for_lux`, by increasing lux
+pub const LUX_CONTRAST_BREAKPOINTS: [(u16, u8); 5] = [(0, 0x10), (10, 0x30), (100, 0x70), (1000, 0xC0), (10000, 0xFF)];
+/// Delay before the init sequence of `init_with_delay`, in milliseconds
+pub const POWER_ON_DELAY_MS: u32 = 100;
+/// Delay before `DisplayON` in `init_with_delay`, in milliseconds
+pub const DISPLAY_ON_DELAY_MS: u32 = 100;
+/// Contrast used by default, 50% (128/255) RESET 0x7f
+pub(crate) const DEFAULT_CONTRAST: u8 = 0x7f;
+/// Remap setting used by default (column address re-map, COM re-map and COM split odd even)
+pub(crate) const DEFAULT_REMAP: u8 = 0x51;
+/// Function selection B used by default, bits 6 and 5 set, second pre-charge disabled and internal VSL
+pub(crate) const DEFAULT_FUNCTION_B: u8 = 0x60;
+/// Phase length used by default, phase 1 of 1 DCLK and phase 2 of 5 DCLKs
+pub(crate) const DEFAULT_PHASE_LENGTH: u8 = 0x51;
+/// COM deselect voltage level used by default, 0.82 x VCC
+pub(crate) const DEFAULT_VCOMH: u8 = 0x05;
+/// Function selection B bit enabling the second pre-charge
+const FUNCTION_B_SECOND_PRECHARGE: u8 = 0x02;
+/// Function selection B bit selecting the external VSL
+const FUNCTION_B_EXTERNAL_VSL: u8 = 0x01;
+/// Remap bits mirroring the display horizontally (column address and nibble re-map)
+const MIRROR_H_REMAP_BITS: u8 = 0x03;
+/// Remap bits mirroring the display vertically (COM re-map)
+const MIRROR_V_REMAP_BITS: u8 = 0x10;
+/// Remap bit enabling vertical address increment
+const VERTICAL_INCREMENT_REMAP_BIT: u8 = 0x04;
+/// Remap bit enabling the COM split odd even
+const COM_SPLIT_REMAP_BIT: u8 = 0x40;
+/// Remap bits flipping the display by 180 degrees
+const FLIP_REMAP_BITS: u8 = MIRROR_H_REMAP_BITS | MIRROR_V_REMAP_BITS;
+/// Largest gap of unchanged bytes merged into a single window by `flush_diff`, cheaper than new address commands
+#[cfg(feature = "framebuffer")]
+pub const DIFF_MERGE_GAP: usize = 8;
+/// Thresholds of the 4x4 ordered dithering used by `draw_gray8`, in sixteenths
+#[cfg(feature = "framebuffer")]
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+/// Gray level written for each of the 16 input levels with gamma correction, `15 * (level / 15)^(1 / 2.2)`
+pub const GAMMA_TABLE: [u8; 16] = [0, 4, 6, 7, 8, 9, 10, 11, 11, 12, 12, 13, 14, 14, 15, 15];
+
+/// Builder to create and initialize a SSD1327I2C object in one step
+/// 
+/// Defaults to slave address 0x3C, the size of the panel selected by the `panel-*` features (128x128 when none
+/// is enabled), no rotation and the default contrast.
+///
+/// `N` is the size of the framebuffer, `buffer_size` of the panel size by default so `SSD1327Builder::new(i2c).build()`
+/// needs no annotation. The size is only set by the constructor, for another size than the panel one `N` is given
+/// along with it and must be `buffer_size(width, height)` (any value with the `alloc` feature):
+///
+/// ```ignore
+/// let driver = SSD1327Builder::<_, { ssd1327_i2c::buffer_size(96, 64) }>::with_size(i2c, 96, 64).build();
+/// ```
+pub struct SSD1327Builder<I2C, const N: usize = { buffer_size(PANEL_WIDTH, PANEL_HEIGHT) }>
+where 
+    I2C: I2c
+{
+    i2c: I2C,
+    slave_address: u8,
+    width: u8,
+    height: u8,
+    rotation: Rotation,
+    contrast: u8,
+}
+
+impl <I2C> SSD1327Builder<I2C>
+where 
+    I2C: I2c,
+{
+    /// Create a new builder for the given I2C peripheral and the panel selected by the `panel-*` features
+    pub fn new(i2c: I2C) -> Self {
+        SSD1327Builder::with_size(i2c, PANEL_WIDTH, PANEL_HEIGHT)
+    }
+}
+
+impl <I2C, const N: usize> SSD1327Builder<I2C, N>
+where 
+    I2C: I2c,
+{
+    /// Create a new builder for the given I2C peripheral and display size, in pixels
+    ///
+    /// `N` must be `buffer_size(width, height)`
+    pub fn with_size(i2c: I2C, width: u8, height: u8) -> Self {
+        SSD1327Builder {
+            i2c,
+            slave_address: 0x3C,
+            width,
+            height,
+            rotation: Rotation::Deg0,
+            contrast: DEFAULT_CONTRAST,
+        }
+    }
+
+    /// Set the slave address
+    pub fn address(mut self, slave_address: u8) -> Self {
+        self.slave_address = slave_address;
+        self
+    }
+
+    /// Set the rotation
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Set the contrast
+    pub fn contrast(mut self, contrast: u8) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    /// Create the SSD1327I2C object without initializing the display
+    pub fn build(self) -> SSD1327I2C<I2C, N> {
+        let mut driver = SSD1327I2C::with_addr_wh(self.i2c, self.slave_address, self.width, self.height);
+        driver.remap ^= self.rotation.remap_bits();
+        driver.rotation = self.rotation;
+        driver.contrast = self.contrast;
+        driver
+    }
+
+    /// Create the SSD1327I2C object and initialize the display
+    pub fn build_and_init(self) -> Result<SSD1327I2C<I2C, N>, I2C::Error> {
+        let mut driver = self.build();
+        driver.init()?;
+        Ok(driver)
+    }
+}
+
+#[cfg(any(
+    all(feature = "panel-128x128", feature = "panel-128x96"),
+    all(feature = "panel-128x128", feature = "panel-96x96"),
+    all(feature = "panel-128x96", feature = "panel-96x96"),
+))]
+compile_error!("only one of the `panel-128x128`, `panel-128x96` and `panel-96x96` features can be enabled, \
+    disable the default features to select a panel other than 128x128");
+
+/// Width of the panel selected by the `panel-*` features, 128 when none is enabled
+#[cfg(not(feature = "panel-96x96"))]
+const PANEL_WIDTH: u8 = 128;
+/// Width of the panel selected by the `panel-*` features
+#[cfg(feature = "panel-96x96")]
+const PANEL_WIDTH: u8 = 96;
+/// Height of the panel selected by the `panel-*` features, 128 when none is enabled
+#[cfg(not(any(feature = "panel-128x96", feature = "panel-96x96")))]
+const PANEL_HEIGHT: u8 = 128;
+/// Height of the panel selected by the `panel-*` features
+#[cfg(any(feature = "panel-128x96", feature = "panel-96x96"))]
+const PANEL_HEIGHT: u8 = 96;
+
+/// MUX ratio of the panel selected by the `panel-*` features, 128x128 when none is enabled
+#[cfg(not(any(feature = "panel-128x96", feature = "panel-96x96")))]
+const DEFAULT_MUX_RATIO: u8 = 0x7e;
+/// MUX ratio of the panel selected by the `panel-*` features, 96 rows
+#[cfg(any(feature = "panel-128x96", feature = "panel-96x96"))]
+const DEFAULT_MUX_RATIO: u8 = 0x5f;
+
+/// Settings sent by `init_with_config`, the defaults suit the panel selected by the `panel-*` features
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayConfig {
+    /// Contrast, see `Commands::ContrastControl` (default 0x7F)
+    pub contrast: u8,
+    /// Re-map setting, see `Commands::Remap` (default 0x51),
+    /// the next `set_rotation`, `set_mirror` or `set_addressing_mode` only change their own bits
+    pub remap: u8,
+    /// MUX ratio, see `Commands::MUXRatio` (default 0x7E, 0x5F with the 96 rows panel features, RESET 0x7F)
+    pub mux_ratio: u8,
+    /// Phase length, see `Commands::PhaseLength` (default 0x51, RESET 0x71)
+    pub phase_length: u8,
+    /// Front clock divider / oscillator frequency, see `Commands::FrontClockDividerOscillatorFrequency` (default 0x00)
+    pub clock_div: u8,
+    /// Second pre-charge period, see `Commands::SecondPreChargePeriod` (default 0x04)
+    pub second_precharge: u8,
+    /// Pre-charge voltage, see `Commands::PreChargeVoltage` (default 0x05)
+    pub precharge: u8,
+    /// COM deselect voltage level, see `Commands::VCOMH` (default 0x05)
+    pub vcomh: u8,
+    /// Function selection B, see `Commands::FunctionSelectionB` (default 0x60)
+    pub function_b: u8,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            contrast: DEFAULT_CONTRAST,
+            remap: DEFAULT_REMAP,
+            mux_ratio: DEFAULT_MUX_RATIO,
+            phase_length: DEFAULT_PHASE_LENGTH,
+            clock_div: 0x00,
+            second_precharge: 0x04,
+            precharge: 0x05,
+            vcomh: DEFAULT_VCOMH,
+            function_b: DEFAULT_FUNCTION_B,
+        }
+    }
+}
+
+/// Procedural pattern drawn by `test_pattern`
+#[cfg(feature = "framebuffer")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TestPattern {
+    /// Alternating black and white pixels, every row and column has lit pixels
+    Checkerboard,
+    /// The 16 gray levels from black on the left to white on the right, reveals dead gray levels
+    Gradient,
+    /// White frame on the edges of the display, shows the orientation and the visible area
+    Borders,
+    /// Alternating black and white rows
+    Stripes,
+}
+
+/// GDDRAM address increment direction after each data byte
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AddressIncrement {
+    /// Next column, then next row at the end of the column window (default)
+    Horizontal,
+    /// Next row, then next column at the end of the row window
+    Vertical,
+}
+
+/// COM deselect voltage levels (VCOMH) defined by the datasheet
+///
+/// | Level    | Register byte | Voltage      |
+/// |----------|---------------|--------------|
+/// | `Vcc072` | 0x00          | 0.72 x VCC   |
+/// | `Vcc082` | 0x05          | 0.82 x VCC   |
+/// | `Vcc086` | 0x07          | 0.86 x VCC   |
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VcomhLevel {
+    /// 0.72 x VCC
+    Vcc072,
+    /// 0.82 x VCC (default and RESET)
+    Vcc082,
+    /// 0.86 x VCC
+    Vcc086,
+}
+
+impl VcomhLevel {
+    /// Register byte of the `Commands::VCOMH` command
+    pub fn value(self) -> u8 {
+        match self {
+            VcomhLevel::Vcc072 => 0x00,
+            VcomhLevel::Vcc082 => 0x05,
+            VcomhLevel::Vcc086 => 0x07,
+        }
+    }
+}
+
+/// Display rotation, clockwise
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rotation {
+    /// No rotation (default)
+    Deg0,
+    /// 90 degrees, width and height are swapped
+    Deg90,
+    /// 180 degrees
+    Deg180,
+    /// 270 degrees, width and height are swapped
+    Deg270,
+}
+
+impl Rotation {
+    /// Remap bits to flip for 180 and 270 degrees
+    pub(crate) fn remap_bits(self) -> u8 {
+        match self {
+            Rotation::Deg0 | Rotation::Deg90 => 0,
+            Rotation::Deg180 | Rotation::Deg270 => FLIP_REMAP_BITS,
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    /// Whether the rotation swaps the width and the height
+    pub(crate) fn swaps_axes(self) -> bool {
+        matches!(self, Rotation::Deg90 | Rotation::Deg270)
+    }
+}
+
+/// Commands to be sent to the SSD1327
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Commands {
+    /// Setup Column start and end address (0x15), in 2 pixels units, see `Commands::column_address`
+    ColumnAddress {
+        /// Start address 00-3f (RESET = 00) 
+        start: u8,
+        /// End address 00-3f (RESET = 3F) 
+        end: u8,
+    },
+    /// Setup Row start and end address (0x75)
+    RowAddress {
+        /// Start address 00-7f (RESET = 00) 
+        start: u8,
+        /// End address 00-7f (RESET = 7F) 
+        end: u8,
+    },
+    /// Double byte command to select 1 out of 256 contrast steps. 
+    /// Contrast increases as the value increases (RESET = 7F ) (0x81)
+    ContrastControl(u8),
+    /// **Re-map setting in Graphic Display Data RAM (GDDRAM)** (0xA0)\
+    /// A[0] = 0, Disable Column Address Re-map (RESET)\
+    /// A[0] = 1, Enable Column Address Re-map\
+    /// A[1] = 0, Disable Nibble Re-map (RESET)\
+    /// A[1] = 1, Enable Nibble Re-map\
+    /// A[2] = 0, Enable Horizontal Address Increment (RESET)\
+    /// A[2] = 1, Enable Vertical Address Increment\
+    /// A[3] = 0, Reserved (RESET)\
+    /// A[4] = 0, Disable COM Re-map (RESET)\
+    /// A[4] = 1, Enable COM Re-map\
+    /// A[5] = 0, Reserved (RESET)\
+    /// A[6] = 0, Disable COM Split Odd Even (RESET)\
+    /// A[6] = 1, Enable COM Split Odd Even\
+    /// A[7] = 0, Reserved (RESET) 
+    Remap(u8),
+    /// Vertical shift by setting the starting address of display RAM from 0 ~ 127 (RESET = 00) (0xA1)
+    DisplayStartLine(u8),
+    /// Set vertical offset by COM from 0 ~ 127 (RESET = 00) (0xA2)
+    DisplayOffset(u8),
+    /// Normal Display Mode (0xA4)
+    DisplayModeNormal,
+    /// All ON Display Mode (0xA5)
+    DisplayModeAllON,
+    /// All OFF Display Mode (0xA6)
+    DisplayModeAllOFF,
+    /// Inverse Display Display Mode (0xA7)
+    DisplayModeInverseDisplay,
+    /// Set MUX ratio from 16MUX ~ 128MUX (0xA8)
+    MUXRatio(u8),
+    /// Function Selection A (0 = external VDD; 1 = internal VDD (RESET)) (0xAB)
+    FunctionSelectionA(u8),
+    /// External VDD (0xAB 0x00)
+    SelectExternalVDD,
+    /// Internal VDD (RESET) (0xAB 0x01)
+    SelectInternalVDD,
+    /// Turn display ON (0xAF)
+    DisplayON,
+    /// Turn display OFF (0xAE)
+    DisplayOFF,
+    /// Phase Length : phase 1 period of 1~15 DCLK's in the low nibble, phase 2 period
+    /// of 1~15 DCLK's in the high nibble (0xB1), see `SSD1327::set_phase_length`
+    PhaseLength(u8),
+    /// Front Clock Divider / Oscillator Frequency (0xB3)
+    FrontClockDividerOscillatorFrequency(u8),
+    /// GPIO : 00 represents GPIO pin HiZ, input disable (always read as low) ; 
+    /// 01 represents GPIO pin HiZ, input enable ; 10 represents GPIO pin output Low (RESET) ; 
+    /// 11 represents GPIO pin output High ; (0xB5)
+    GPIO(u8),
+    /// Second Pre-charge period of 1~15 DCLK’s (RESET = 0100) (0xB6),
+    /// sent as is, other values are undefined, see `SSD1327::set_second_precharge`
+    SecondPreChargePeriod(u8),
+    /// Gray Scale table, pulse widths of gray levels GS1 to GS15 in DCLKs, 
+    /// values range from 0 to 180 and must increase with the gray level (0xB8)
+    GrayScaleTable([u8; 15]),
+    /// The default Lineear Gray Scale table (0xB9)
+    LinearLUT,
+    /// Set pre-charge voltage level (0xBC)
+    PreChargeVoltage(u8),
+    /// Set COM deselect voltage level (0xBE)
+    VCOMH(u8),
+    /// Function Selection B : 0.: Disable second precharge (RESET) ; 1.: Enable second precharge ; 
+    /// .0: Internal VSL (RESET) ; .1: Enable external VSL ; (0xD5)
+    FunctionSelectionB(u8),
+    /// No operation (0xE3)
+    Nop,
+    /// MCU protection status 0x16 = Lock ; 0x12 Unlock (RESET) ; (0xFD)
+    SetCommandLock(u8),
+    /// Unlock OLED driver IC MCU interface from entering commands (RESET) (0xFD 0x12)
+    CommandUnlock,
+    /// Lock OLED driver IC MCU interface from entering commands (0xFD 0x16)
+    CommandLock,
+}
+
+impl Commands {
+    /// Column address window from `start` to `end` inclusive, `None` unless `start <= end <= 0x3F`
+    ///
+    /// Each column address holds two pixels, so the column `c` covers the pixels `2c` and `2c + 1`.
+    pub fn column_address(start: u8, end: u8) -> Option<Commands> {
+        (start <= end && end <= MAX_COLUMN_ADDRESS).then_some(Commands::ColumnAddress { start, end })
+    }
+
+    /// Row address window from `start` to `end` inclusive, `None` unless `start <= end <= 0x7F`
+    pub fn row_address(start: u8, end: u8) -> Option<Commands> {
+        (start <= end && end <= MAX_ROW_ADDRESS).then_some(Commands::RowAddress { start, end })
+    }
+
+    /// Encode the command into `buf` as written to the SSD1327 over I2C, the command control byte
+    /// followed by the command bytes, and return the encoded length
+    ///
+    /// # Panics
+    ///
+    /// If `buf` is too short for the command, `MAX_CMD_LEN` bytes fit any command.
+    pub fn encode(&self, buf: &mut [u8]) -> usize {
+        // 0x00 = Command stream (Co = 0), see `I2cInterface::set_command_control_byte` for 0x80
+        let (bytes, len) = match *self {
+            Commands::ColumnAddress { start, end } => ([0x00, 0x15, start, end], 4),
+            Commands::RowAddress { start, end } => ([0x00, 0x75, start, end], 4),
+            Commands::ContrastControl(value) => ([0x00, 0x81, value, 0], 3),
+            Commands::Remap(value) => ([0x00, 0xA0, value, 0], 3),
+            Commands::DisplayStartLine(value) => ([0x00, 0xA1, value, 0], 3),
+            Commands::DisplayOffset(value) => ([0x00, 0xA2, value, 0], 3),
+            Commands::DisplayModeNormal => ([0x00, 0xA4, 0, 0], 2),
+            Commands::DisplayModeAllON => ([0x00, 0xA5, 0, 0], 2),
+            Commands::DisplayModeAllOFF => ([0x00, 0xA6, 0, 0], 2),
+            Commands::DisplayModeInverseDisplay => ([0x00, 0xA7, 0, 0], 2),
+            Commands::MUXRatio(value) => ([0x00, 0xA8, value, 0], 3),
+            Commands::FunctionSelectionA(value) => ([0x00, 0xAB, value, 0], 3),
+            Commands::SelectExternalVDD => ([0x00, 0xAB, 0x00, 0], 3),
+            Commands::SelectInternalVDD => ([0x00, 0xAB, 0x01, 0], 3),
+            Commands::DisplayON => ([0x00, 0xAF, 0, 0], 2),
+            Commands::DisplayOFF => ([0x00, 0xAE, 0, 0], 2),
+            Commands::PhaseLength(value) => ([0x00, 0xB1, value, 0], 3),
+            Commands::FrontClockDividerOscillatorFrequency(value) => ([0x00, 0xB3, value, 0], 3),
+            Commands::GPIO(value) => ([0x00, 0xB5, value, 0], 3),
+            Commands::SecondPreChargePeriod(value) => ([0x00, 0xB6, value, 0], 3),
+            Commands::GrayScaleTable(table) => {
+                buf[0..2].copy_from_slice(&[0x00, 0xB8]);
+                buf[2..MAX_CMD_LEN].copy_from_slice(&table);
+                return MAX_CMD_LEN;
+            }
+            Commands::LinearLUT => ([0x00, 0xB9, 0, 0], 2),
+            Commands::PreChargeVoltage(value) => ([0x00, 0xBC, value, 0], 3),
+            Commands::VCOMH(value) => ([0x00, 0xBE, value, 0], 3),
+            Commands::FunctionSelectionB(value) => ([0x00, 0xD5, value, 0], 3),
+            Commands::Nop => ([0x00, 0xE3, 0, 0], 2),
+            Commands::SetCommandLock(value) => ([0x00, 0xFD, value, 0], 3),
+            Commands::CommandUnlock => ([0x00, 0xFD, 0x00, 0x12], 4),
+            Commands::CommandLock => ([0x00, 0xFD, 0x00, 0x16], 4),
+        };
+        buf[0..len].copy_from_slice(&bytes[0..len]);
+        len
+    }
+}
+
+/// Command bytes of several commands packed back to back, see `command_batches`
+pub(crate) struct CommandBatches<'a> {
+    cmds: &'a [Commands],
+}
+
+impl Iterator for CommandBatches<'_> {
+    /// Bytes of the batch and their number
+    type Item = ([u8; CMDS_BUFFER_LEN - 1], usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cmds.is_empty() {
+            return None;
+        }
+        // Room for the I2C control byte
+        let mut bytes = [0u8; CMDS_BUFFER_LEN - 1];
+        let mut len = 0;
+        while let Some((cmd, rest)) = self.cmds.split_first() {
+            let mut data = [0u8; MAX_CMD_LEN];
+            let cmd_len = cmd.encode(&mut data);
+            // Skip the control byte of each command
+            let cmd_bytes = &data[1..cmd_len];
+            if len + cmd_bytes.len() > bytes.len() {
+                break;
+            }
+            bytes[len..len + cmd_bytes.len()].copy_from_slice(cmd_bytes);
+            len += cmd_bytes.len();
+            self.cmds = rest;
+        }
+        Some((bytes, len))
+    }
+}
+
+/// Command bytes of `cmds` without control bytes, in batches of at most `CMDS_BUFFER_LEN - 1` bytes
+///
+/// Shared by the blocking and async drivers, each batch is sent after a single command control byte.
+pub(crate) fn command_batches(cmds: &[Commands]) -> CommandBatches<'_> {
+    CommandBatches { cmds }
+}
+
+/// Command sequence used to initialize a SSD1327 with the given address window, see `full_address_window`
+pub(crate) fn init_commands(window: [Commands; 2], cfg: &DisplayConfig) -> [Commands; 19] {
+    let [column_address, row_address] = window;
+    [
+        Commands::CommandUnlock,
+        Commands::DisplayOFF,
+        column_address,
+        row_address,
+        Commands::ContrastControl(cfg.contrast),
+        Commands::Remap(cfg.remap),
+        Commands::DisplayStartLine(0x00),
+        Commands::DisplayOffset(0x00),
+        Commands::DisplayModeNormal,
+        Commands::MUXRatio(cfg.mux_ratio),
+        Commands::PhaseLength(cfg.phase_length),
+        Commands::LinearLUT,
+        Commands::FrontClockDividerOscillatorFrequency(cfg.clock_div),
+        Commands::SelectInternalVDD,
+        Commands::SecondPreChargePeriod(cfg.second_precharge),
+        Commands::VCOMH(cfg.vcomh),
+        Commands::PreChargeVoltage(cfg.precharge),
+        Commands::FunctionSelectionB(cfg.function_b),
+        Commands::DisplayON,
+    ]
+}
+
+/// Column and row address commands of the given window, in display columns and rows, shifted by the `(column, row)` offsets
+pub(crate) fn address_window(offset: (u8, u8), col_start: u8, col_end: u8, row_start: u8, row_end: u8) -> [Commands; 2] {
+    let (col_offset, row_offset) = offset;
+    [
+        Commands::ColumnAddress { start: col_start + col_offset, end: col_end + col_offset },
+        Commands::RowAddress { start: row_start + row_offset, end: row_end + row_offset },
+    ]
+}
+
+/// Column and row address commands covering the whole GDDRAM of a display of the given width and height
+///
+/// The rotation is applied when drawing in the framebuffer, the GDDRAM window never depends on it.
+/// Shared by the blocking and async drivers, shifted by the `(column, row)` offsets of `with_offset`.
+pub(crate) fn full_address_window(width: u8, height: u8, offset: (u8, u8)) -> [Commands; 2] {
+    // Two pixels per column
+    address_window(offset, 0, (row_bytes(width) - 1) as u8, 0, height - 1)
+}
+
+/// Column and row offsets clamped so that a display of the given width and height still fits the GDDRAM
+pub(crate) fn clamp_offset(width: u8, height: u8, col_offset: u8, row_offset: u8) -> (u8, u8) {
+    let columns = row_bytes(width).min(MAX_COLUMN_ADDRESS as usize + 1) as u8;
+    (
+        col_offset.min(MAX_COLUMN_ADDRESS + 1 - columns),
+        row_offset.min(MAX_ROW_ADDRESS + 1 - height.min(MAX_ROW_ADDRESS + 1)),
+    )
+}
+
+/// Contrast for the given ambient light in lux, interpolated linearly between `LUX_CONTRAST_BREAKPOINTS`
+///
+/// The breakpoints grow tenfold, so the curve is roughly logarithmic like the perceived brightness:
+///
+/// | Lux    | Contrast |
+/// |--------|----------|
+/// | 0      | 0x10     |
+/// | 10     | 0x30     |
+/// | 100    | 0x70     |
+/// | 1000   | 0xC0     |
+/// | 10000+ | 0xFF     |
+pub fn contrast_for_lux(lux: u16) -> u8 {
+    for pair in LUX_CONTRAST_BREAKPOINTS.windows(2) {
+        let ((lux_low, contrast_low), (lux_high, contrast_high)) = (pair[0], pair[1]);
+        if lux < lux_high {
+            let span = (contrast_high - contrast_low) as u32 * (lux - lux_low) as u32 / (lux_high - lux_low) as u32;
+            return contrast_low + span as u8;
+        }
+    }
+    LUX_CONTRAST_BREAKPOINTS[LUX_CONTRAST_BREAKPOINTS.len() - 1].1
+}
+
+/// Send a data chunk, a second time if the first transfer fails and `retry` is set
+fn send_chunk<DI: Interface>(interface: &mut DI, chunk: &[u8], retry: bool) -> Result<(), DI::Error> {
+    match interface.send_data(chunk) {
+        Err(_) if retry => interface.send_data(chunk),
+        res => res,
+    }
+}
+
+/// Write a gray value in the framebuffer of a display of the given width at the given pixel coordinates
+#[cfg(feature = "framebuffer")]
+pub(crate) fn write_pixel(framebuffer: &mut [u8], width: u8, x: u32, y: u32, luma: u8) {
+    // Calculate the index in the framebuffer.
+    let index = x as usize / 2 + y as usize * row_bytes(width);
+    // Out of range values would overwrite the other pixel of the byte
+    let mut new_byte = luma & 0x0F;
+    // 1 byte for 2 pixels so we need to shift the byte by 4 bits if the x coordinate is even
+    if x.is_multiple_of(2) {
+        new_byte <<= 4;
+        framebuffer[index] &= 0x0F;
+    } else {
+        framebuffer[index] &= 0xF0;
+    }
+    framebuffer[index] |= new_byte;
+}
+
+#[cfg(feature = "graphics")]
+impl <DI, const N: usize, RST, FB> DrawTarget for SSD1327<DI, N, RST, FB>
+where 
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+
+    type Color = Gray4;
+
+    type Error = DI::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        // True width and height as seen with the current rotation
+        let (width, height) = self.rotated_dimensions();
+        for Pixel(coord, color) in pixels.into_iter() {
+            // Skip the pixels out of bounds, negative coordinates fail the conversion
+            if let Ok((x, y)) = <(u32, u32)>::try_from(coord) {
+                if x < width as u32 && y < height as u32 {
+                    self.set_pixel_raw(x as u8, y as u8, self.luma(color));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        // Rows of the framebuffer are columns of the area when rotated by 90 or 270 degrees
+        if self.rotation.swaps_axes() {
+            return self.draw_iter(area.points().zip(colors).map(|(pos, color)| Pixel(pos, color)));
+        }
+        let Some(bottom_right) = area.bottom_right() else {
+            return Ok(());
+        };
+        let (width, height) = (self.width as i32, self.height as i32);
+        let mut colors = colors.into_iter();
+        for y in area.top_left.y..=bottom_right.y {
+            let mut x = area.top_left.x;
+            while x <= bottom_right.x {
+                let Some(color) = colors.next() else {
+                    return Ok(());
+                };
+                let visible = (0..width).contains(&x) && (0..height).contains(&y);
+                // Both pixels of a byte are in the area, write the whole byte at once
+                if visible && x % 2 == 0 && x < bottom_right.x && x + 1 < width {
+                    let Some(next) = colors.next() else {
+                        self.set_pixel_raw(x as u8, y as u8, self.luma(color));
+                        return Ok(());
+                    };
+                    self.framebuffer.as_mut()[x as usize / 2 + y as usize * row_bytes(self.width)] = (self.luma(color) << 4) | self.luma(next);
+                    self.mark_pixel_dirty(x as u8, y as u8);
+                    self.mark_pixel_dirty(x as u8 + 1, y as u8);
+                    x += 2;
+                } else {
+                    if visible {
+                        self.set_pixel_raw(x as u8, y as u8, self.luma(color));
+                    }
+                    x += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        // Same color everywhere, whatever the rotation
+        if area == self.bounding_box() {
+            SSD1327::clear(self, color);
+            return Ok(());
+        }
+        if self.rotation.swaps_axes() {
+            return self.fill_contiguous(&area, core::iter::repeat(color));
+        }
+        let Some(bottom_right) = area.bottom_right() else {
+            return Ok(());
+        };
+        let luma = self.luma(color);
+        let (left, right) = (area.top_left.x as usize, bottom_right.x as usize);
+        // Whole bytes between the odd left edge and the even right edge, filled at once
+        let (first_byte, end_byte) = (left.div_ceil(2), right.div_ceil(2));
+        for y in area.top_left.y as u8..=bottom_right.y as u8 {
+            let row = y as usize * row_bytes(self.width);
+            self.framebuffer.as_mut()[row + first_byte..row + end_byte].fill((luma << 4) | luma);
+            if left % 2 == 1 {
+                self.set_pixel_raw(left as u8, y, luma);
+            }
+            if right % 2 == 0 {
+                self.set_pixel_raw(right as u8, y, luma);
+            }
+        }
+        self.mark_pixel_dirty(left as u8, area.top_left.y as u8);
+        self.mark_pixel_dirty(right as u8, bottom_right.y as u8);
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        SSD1327::clear(self, color);
+        Ok(())
+    }
+
+}
+
+#[cfg(feature = "graphics")]
+impl <DI, const N: usize, RST, FB> OriginDimensions for SSD1327<DI, N, RST, FB>
+where 
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// True width and height as seen with the current rotation, the bounding box used by
+    /// `DrawTargetExt` adapters like `clipped` and `translated` starts at (0, 0)
+    fn size(&self) -> Size {
+        let (width, height) = self.rotated_dimensions();
+        Size::new(width as u32, height as u32)
+    }
+}
+
+#[cfg(feature = "framebuffer")]
+impl <DI, const N: usize, RST, FB> Index<(u8, u8)> for SSD1327<DI, N, RST, FB>
+where 
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Output = u8;
+
+    /// Packed framebuffer byte holding the pixel at (x, y), the even framebuffer x in the high nibble
+    ///
+    /// Coordinates are relative to the current rotation, use `get_pixel` for the 4-bit gray value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if (x, y) is out of bounds.
+    fn index(&self, (x, y): (u8, u8)) -> &u8 {
+        let Some((x, y)) = self.framebuffer_coordinates(x, y) else {
+            panic!("pixel ({}, {}) out of bounds", x, y);
+        };
+        &self.framebuffer.as_ref()[x as usize / 2 + y as usize * row_bytes(self.width)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDelay, MockI2c, MockPin, MockSpi};
+    use core::cell::Cell;
+    use core::convert::Infallible;
+    use embedded_hal::digital::ErrorType;
+
+    /// Framebuffer of a 128x128 display counting its mutable accesses, one per pixel when drawn pixel by pixel
+    #[cfg(feature = "graphics")]
+    struct CountingBuffer([u8; buffer_size(128, 128)], usize);
+
+    #[cfg(feature = "graphics")]
+    impl AsRef<[u8]> for CountingBuffer {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    impl AsMut<[u8]> for CountingBuffer {
+        fn as_mut(&mut self) -> &mut [u8] {
+            self.1 += 1;
+            &mut self.0
+        }
+    }
+
+    #[test]
+    fn commands_encoding() {
+        let table: [(Commands, &[u8]); 29] = [
+            (Commands::ColumnAddress { start: 0x01, end: 0x3F }, &[0x00, 0x15, 0x01, 0x3F]),
+            (Commands::RowAddress { start: 0x02, end: 0x7F }, &[0x00, 0x75, 0x02, 0x7F]),
+            (Commands::ContrastControl(0x42), &[0x00, 0x81, 0x42]),
+            (Commands::Remap(0x51), &[0x00, 0xA0, 0x51]),
+            (Commands::DisplayStartLine(0x10), &[0x00, 0xA1, 0x10]),
+            (Commands::DisplayOffset(0x20), &[0x00, 0xA2, 0x20]),
+            (Commands::DisplayModeNormal, &[0x00, 0xA4]),
+            (Commands::DisplayModeAllON, &[0x00, 0xA5]),
+            (Commands::DisplayModeAllOFF, &[0x00, 0xA6]),
+            (Commands::DisplayModeInverseDisplay, &[0x00, 0xA7]),
+            (Commands::MUXRatio(0x7E), &[0x00, 0xA8, 0x7E]),
+            (Commands::FunctionSelectionA(0x01), &[0x00, 0xAB, 0x01]),
+            (Commands::SelectExternalVDD, &[0x00, 0xAB, 0x00]),
+            (Commands::SelectInternalVDD, &[0x00, 0xAB, 0x01]),
+            (Commands::DisplayON, &[0x00, 0xAF]),
+            (Commands::DisplayOFF, &[0x00, 0xAE]),
+            (Commands::PhaseLength(0x51), &[0x00, 0xB1, 0x51]),
+            (Commands::FrontClockDividerOscillatorFrequency(0x01), &[0x00, 0xB3, 0x01]),
+            (Commands::GPIO(0x02), &[0x00, 0xB5, 0x02]),
+            (Commands::SecondPreChargePeriod(0x04), &[0x00, 0xB6, 0x04]),
+            (
+                Commands::GrayScaleTable([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
+                &[0x00, 0xB8, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            ),
+            (Commands::LinearLUT, &[0x00, 0xB9]),
+            (Commands::PreChargeVoltage(0x05), &[0x00, 0xBC, 0x05]),
+            (Commands::VCOMH(0x07), &[0x00, 0xBE, 0x07]),
+            (Commands::FunctionSelectionB(0x62), &[0x00, 0xD5, 0x62]),
+            (Commands::Nop, &[0x00, 0xE3]),
+            (Commands::SetCommandLock(0x16), &[0x00, 0xFD, 0x16]),
+            (Commands::CommandUnlock, &[0x00, 0xFD, 0x00, 0x12]),
+            (Commands::CommandLock, &[0x00, 0xFD, 0x00, 0x16]),
+        ];
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        for (index, (cmd, bytes)) in table.iter().enumerate() {
+            let mut buf = [0u8; MAX_CMD_LEN];
+            let len = cmd.encode(&mut buf);
+            assert_eq!(&buf[0..len], *bytes, "{:?}", cmd);
+            driver.send_cmd(*cmd).unwrap();
+            assert_eq!(driver.interface.i2c.transfer(index), *bytes, "{:?}", cmd);
+        }
+        assert_eq!(driver.interface.i2c.count(), table.len());
+    }
+
+    #[test]
+    fn address_window_validation() {
+        assert_eq!(Commands::column_address(0, 0x3F), Some(Commands::ColumnAddress { start: 0, end: 0x3F }));
+        assert_eq!(Commands::column_address(0x10, 0x10), Some(Commands::ColumnAddress { start: 0x10, end: 0x10 }));
+        assert_eq!(Commands::column_address(0, 0x40), None);
+        assert_eq!(Commands::column_address(0x20, 0x1F), None);
+        assert_eq!(Commands::row_address(0, 0x7F), Some(Commands::RowAddress { start: 0, end: 0x7F }));
+        assert_eq!(Commands::row_address(0, 0x80), None);
+    }
+
+    #[test]
+    fn init_sends_a_single_transfer() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.init().unwrap();
+        assert_eq!(driver.interface.i2c.count(), 1);
+        let transfer = driver.interface.i2c.transfer(0);
+        assert_eq!(transfer.len(), 38);
+        assert_eq!(&transfer[0..11], &[0x00, 0xFD, 0x00, 0x12, 0xAE, 0x15, 0x00, 0x3F, 0x75, 0x00, 0x7F]);
+        assert_eq!(transfer[37], 0xAF);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn framebuffer_packing() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        driver.set_pixel(2, 1, 0xA);
+        driver.set_pixel(3, 1, 0x5);
+        assert_eq!(driver.framebuffer().len(), 256);
+        assert_eq!(driver.framebuffer()[16 + 1], 0xA5);
+        driver.framebuffer_mut()[0] = 0x12;
+        assert_eq!(driver.framebuffer()[0], 0x12);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn flush_region_window_wraps() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        for (index, byte) in driver.framebuffer_mut().iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        // Columns 1 to 3 (3 bytes wide) of rows 1 and 2
+        driver.flush_region(2, 1, 6, 2).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x15, 1, 3]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0x75, 1, 2]);
+        assert_eq!(driver.interface.i2c.transfer(2), &[0x40, 17, 18, 19, 33, 34, 35]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn flush_all_with_calls_back_per_chunk() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        let mut chunks = 0;
+        driver.flush_all_with(|| chunks += 1).unwrap();
+        assert_eq!(chunks, 256);
+        driver.set_chunk_len(MAX_CHUNK_LEN);
+        chunks = 0;
+        driver.flush_all_with(|| chunks += 1).unwrap();
+        assert_eq!(chunks, 8192usize.div_ceil(MAX_CHUNK_LEN));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn full_window_matches_dimensions() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 96, 64);
+        driver.set_rotation(Rotation::Deg90).unwrap();
+        driver.init().unwrap();
+        driver.flush_all().unwrap();
+        let window = [0x15, 0, 47, 0x75, 0, 63];
+        assert_eq!(&driver.interface.i2c.transfer(1)[5..11], &window);
+        assert_eq!(&driver.interface.i2c.transfer(2)[1..], &window);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn flush_all_ignores_dirty_state() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        driver.flush().unwrap();
+        assert_eq!(driver.interface.i2c.count(), 0);
+        driver.flush_all().unwrap();
+        let data_len: usize = driver.interface.i2c.transfers().skip(1).map(|transfer| transfer.len() - 1).sum();
+        assert_eq!(data_len, 256);
+        // The next flush only sends the modified byte
+        driver.set_pixel(4, 2, 0xF);
+        driver.flush().unwrap();
+        assert_eq!(driver.interface.i2c.transfers().last(), Some(&[0x40, 0xF0][..]));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn multi_display_routes_pixels() {
+        use embedded_graphics_core::geometry::Point;
+
+        let left = build_ssd1327_i2c!(MockI2c::new(), 0x3C, 16, 16);
+        let right = build_ssd1327_i2c!(MockI2c::new(), 0x3C, 16, 16);
+        assert_eq!(MultiDisplay::new([left, right]).err(), Some(DuplicateAddress(0x3C)));
+
+        let left = build_ssd1327_i2c!(MockI2c::new(), 0x3C, 16, 16);
+        let right = build_ssd1327_i2c!(MockI2c::new(), 0x3D, 16, 16);
+        let mut display = MultiDisplay::new([left, right]).unwrap();
+        assert_eq!(display.size(), Size::new(32, 16));
+        let white = Gray4::new(0xF);
+        display.draw_iter([Pixel(Point::new(3, 1), white), Pixel(Point::new(20, 2), white)]).unwrap();
+        assert_eq!(display.panels()[0].get_pixel(3, 1), Some(0xF));
+        assert_eq!(display.panels()[1].get_pixel(4, 2), Some(0xF));
+        assert_eq!(display.panels()[0].get_pixel(4, 2), Some(0));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn color_converted_maps_to_gray4() {
+        use embedded_graphics_core::geometry::Point;
+        use embedded_graphics_core::pixelcolor::{BinaryColor, Gray2};
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.color_converted().draw_iter([Pixel(Point::new(0, 0), BinaryColor::On)]).unwrap();
+        driver.color_converted().draw_iter([Pixel(Point::new(1, 0), Gray2::new(1))]).unwrap();
+        assert_eq!(driver.get_pixel(0, 0), Some(15));
+        assert_eq!(driver.get_pixel(1, 0), Some(5));
+        driver.color_converted().clear(BinaryColor::Off).unwrap();
+        assert_eq!(driver.get_pixel(0, 0), Some(0));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn clipped_draws_only_inside() {
+        use embedded_graphics::draw_target::DrawTargetExt;
+        use embedded_graphics_core::geometry::{Dimensions, Point};
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        assert_eq!(driver.bounding_box(), Rectangle::new(Point::zero(), Size::new(32, 16)));
+        let area = Rectangle::new(Point::new(2, 3), Size::new(4, 5));
+        driver.clipped(&area).clear(Gray4::new(0xF)).unwrap();
+        let lit = (0..16).flat_map(|y| (0..32).map(move |x| (x, y))).filter(|&(x, y)| driver.get_pixel(x, y) == Some(0xF));
+        assert!(lit.clone().all(|(x, y)| area.contains(Point::new(x as i32, y as i32))));
+        assert_eq!(lit.count(), 20);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn draw_iter_bounds() {
+        use embedded_graphics_core::geometry::Point;
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        let white = Gray4::new(0xF);
+        driver.draw_iter([Pixel(Point::new(0, 0), white), Pixel(Point::new(31, 15), white)]).unwrap();
+        assert_eq!(driver.framebuffer()[0], 0xF0);
+        assert_eq!(driver.framebuffer()[255], 0x0F);
+        driver.draw_iter([Pixel(Point::new(32, 16), white), Pixel(Point::new(-1, -1), white)]).unwrap();
+        assert_eq!(driver.framebuffer().iter().filter(|&&byte| byte != 0).count(), 2);
+    }
+
+    #[cfg(all(feature = "graphics", not(feature = "alloc")))]
+    #[test]
+    #[should_panic(expected = "buffer_size")]
+    fn framebuffer_size_mismatch_panics() {
+        SSD1327I2C::<_, { buffer_size(32, 32) }>::with_wh(MockI2c::new(), 32, 16);
+    }
+
+    #[cfg(all(feature = "graphics", feature = "alloc"))]
+    #[test]
+    fn alloc_framebuffer_sized_at_runtime() {
+        let driver = SSD1327I2C::<_>::with_wh(MockI2c::new(), 96, 64);
+        assert_eq!(driver.framebuffer().len(), buffer_size(96, 64));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn get_pixel_round_trip() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        for gray in 0..16 {
+            driver.set_pixel(gray, 10, gray);
+            driver.set_pixel(127 - gray, 127, gray);
+        }
+        for gray in 0..16 {
+            assert_eq!(driver.get_pixel(gray, 10), Some(gray));
+            assert_eq!(driver.get_pixel(127 - gray, 127), Some(gray));
+        }
+        assert_eq!(driver.get_pixel(128, 0), None);
+        assert_eq!(driver.get_pixel(0, 128), None);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn draw_image_raw_even_and_odd_x() {
+        // 8x8 image, gray value of each pixel is its x coordinate
+        let image = [0x01, 0x23, 0x45, 0x67].repeat(8);
+        for x in [10, 11] {
+            let mut driver = SSD1327I2C::new(MockI2c::new());
+            driver.draw_image_raw(x, 20, 8, &image);
+            for py in 20..28 {
+                for px in 0..8 {
+                    assert_eq!(driver.get_pixel(x + px, py), Some(px));
+                }
+                assert_eq!(driver.get_pixel(x - 1, py), Some(0));
+                assert_eq!(driver.get_pixel(x + 8, py), Some(0));
+            }
+        }
+    }
+
+    #[test]
+    fn rotation_and_mirror_compose() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.set_mirror(true, false).unwrap();
+        driver.set_rotation(Rotation::Deg180).unwrap();
+        assert_eq!(driver.remap(), 0x51 ^ 0x03 ^ 0x13);
+        driver.set_addressing_mode(AddressIncrement::Vertical).unwrap();
+        driver.set_rotation(Rotation::Deg0).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(3), &[0x00, 0xA0, (0x51 ^ 0x03) | 0x04]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn addressing_mode_remap_bit() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.set_addressing_mode(AddressIncrement::Vertical).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0xA0, 0x55]);
+        // Flushing switches back to horizontal increment
+        driver.flush_region(0, 0, 2, 1).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0xA0, 0x51]);
+        assert_eq!(driver.addressing_mode(), AddressIncrement::Horizontal);
+    }
+
+    #[test]
+    fn fade_to_steps() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        let mut delay = MockDelay(0);
+        driver.set_contrast(0).unwrap();
+        driver.fade_to(100, 4, &mut delay, 10).unwrap();
+        driver.fade_to(0, 2, &mut delay, 10).unwrap();
+        let values: heapless::Vec<u8, 8> = driver.interface.i2c.transfers().skip(1).map(|transfer| transfer[2]).collect();
+        assert_eq!(&values[..], &[25, 50, 75, 100, 50, 0]);
+        assert_eq!(delay.0, 60_000_000);
+    }
+
+    #[test]
+    fn sleep_and_wake_restore_contrast() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.set_contrast(0x40).unwrap();
+        driver.sleep().unwrap();
+        assert!(!driver.is_on());
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0x81, 0x00, 0xAE]);
+        driver.wake().unwrap();
+        assert!(driver.is_on());
+        assert_eq!(driver.contrast(), 0x40);
+        assert_eq!(driver.interface.i2c.transfer(2), &[0x00, 0x81, 0x40, 0xAF]);
+    }
+
+    #[test]
+    fn deep_sleep_restores_internal_vdd_on_wake() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.deep_sleep().unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x81, 0x00, 0xAE, 0xAB, 0x00]);
+        driver.wake().unwrap();
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0xAB, 0x01, 0x81, DEFAULT_CONTRAST, 0xAF]);
+        // Back on the internal VDD, the next wake leaves it alone
+        driver.sleep().unwrap();
+        driver.wake().unwrap();
+        assert_eq!(driver.interface.i2c.transfer(3), &[0x00, 0x81, DEFAULT_CONTRAST, 0xAF]);
+    }
+
+    #[cfg(feature = "text")]
+    #[test]
+    fn text_wraps_and_scrolls() {
+        use core::fmt::Write;
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        write!(driver, "|").unwrap();
+        // Middle column of '|'
+        assert_eq!(driver.get_pixel(2, 0), Some(0xF));
+        assert_eq!(driver.get_pixel(1, 0), Some(0));
+        assert_eq!(driver.cursor(), (6, 0));
+        // Two characters per line, the third one wraps
+        write!(driver, "||").unwrap();
+        assert_eq!(driver.cursor(), (6, 8));
+        assert_eq!(driver.get_pixel(2, 8), Some(0xF));
+        // No room for a third line, the text scrolls up
+        writeln!(driver).unwrap();
+        assert_eq!(driver.cursor(), (0, 8));
+        assert_eq!(driver.get_pixel(2, 0), Some(0xF));
+        assert_eq!(driver.get_pixel(2, 8), Some(0));
+    }
+
+    #[test]
+    fn mux_ratio_bounds() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.set_mux_ratio(16).unwrap();
+        assert_eq!(driver.mux_ratio(), 16);
+        driver.set_mux_ratio(128).unwrap();
+        assert_eq!(driver.mux_ratio(), 128);
+        driver.set_mux_ratio(8).unwrap();
+        assert_eq!(driver.mux_ratio(), 16);
+        driver.set_display_offset(32).unwrap();
+        assert_eq!(driver.display_offset(), 32);
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0xA8, 15]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0xA8, 127]);
+        assert_eq!(driver.interface.i2c.transfer(2), &[0x00, 0xA8, 15]);
+        assert_eq!(driver.interface.i2c.transfer(3), &[0x00, 0xA2, 32]);
+    }
+
+    #[test]
+    fn scroll_vertical_wraps() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.scroll_vertical(127).unwrap();
+        assert_eq!(driver.start_line(), 127);
+        driver.scroll_vertical(1).unwrap();
+        assert_eq!(driver.start_line(), 0);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0xA1, 0x00]);
+    }
+
+    #[test]
+    fn reset_pulses_reset_pin() {
+        struct Pin<'a>(&'a mut heapless::Vec<bool, 4>);
+        impl ErrorType for Pin<'_> {
+            type Error = Infallible;
+        }
+        impl OutputPin for Pin<'_> {
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                self.0.push(false).unwrap();
+                Ok(())
+            }
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                self.0.push(true).unwrap();
+                Ok(())
+            }
+        }
+        let mut levels = heapless::Vec::new();
+        let mut delay = MockDelay(0);
+        let mut driver = SSD1327I2C::new(MockI2c::new()).with_reset_pin(Pin(&mut levels));
+        driver.init().unwrap();
+        driver.reset(&mut delay).unwrap();
+        assert!(!driver.is_on());
+        drop(driver);
+        assert_eq!(&levels[..], &[false, true]);
+        assert!(delay.0 >= 100_003_000);
+    }
+
+    #[test]
+    fn init_with_delay_waits_before_display_on() {
+        let mut delay = MockDelay(0);
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.init_with_delay(&mut delay).unwrap();
+        assert!(driver.is_on());
+        assert_eq!(driver.interface.i2c.count(), 2);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0xAF]);
+        assert_eq!(delay.0, 200_000_000);
+    }
+
+    #[test]
+    fn try_with_addr_wh_checks_geometry() {
+        assert_eq!(SSD1327I2C::<_, 0>::try_with_addr_wh(MockI2c::new(), 0x3C, 0, 64).err(), Some(Error::InvalidGeometry));
+        assert_eq!(SSD1327I2C::<_, 12800>::try_with_addr_wh(MockI2c::new(), 0x3C, 200, 128).err(), Some(Error::InvalidGeometry));
+        assert!(SSD1327I2C::<_, { buffer_size(96, 64) }>::try_with_addr_wh(MockI2c::new(), 0x3C, 96, 64).is_ok());
+    }
+
+    #[test]
+    fn single_command_control_byte() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.set_command_control_byte(0x80);
+        driver.send_cmd(Commands::ContrastControl(0x20)).unwrap();
+        driver.send_cmds(&[Commands::DisplayOFF, Commands::DisplayON]).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x80, 0x81, 0x80, 0x20]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x80, 0xAE, 0x80, 0xAF]);
+    }
+
+    #[test]
+    fn into_inner_gives_i2c_back() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.display_on().unwrap();
+        let i2c = driver.into_inner();
+        assert_eq!(i2c.transfer(0), &[0x00, 0xAF]);
+    }
+
+    #[test]
+    fn probe_reports_nack() {
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+
+        let mut driver = SSD1327I2C::new(MockI2c::at_address(0x3C));
+        assert!(driver.probe().is_ok());
+        let mut driver = SSD1327I2C::with_addr(MockI2c::at_address(0x3C), 0x3D);
+        assert_eq!(driver.probe(), Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)));
+        driver.set_slave_address(0x3C);
+        assert_eq!(driver.slave_address(), 0x3C);
+        assert!(driver.probe().is_ok());
+    }
+
+    #[test]
+    fn write_raw_at_sets_window() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.write_raw_at(2, 1, 4, 2, &[0x12, 0x34, 0x56, 0x78]).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x15, 1, 2, 0x75, 1, 2]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x40, 0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn spi_sets_dc_pin() {
+        let dc = Cell::new(true);
+        let mut driver = SSD1327Spi::new(MockSpi::new(&dc), MockPin(&dc));
+        driver.send_cmds(&[Commands::DisplayOFF, Commands::ContrastControl(0x20)]).unwrap();
+        driver.send_data(&[0x12, 0x34]).unwrap();
+        assert_eq!(driver.interface.spi.write(0), (false, &[0xAE, 0x81, 0x20][..]));
+        assert_eq!(driver.interface.spi.write(1), (true, &[0x12, 0x34][..]));
+    }
+
+    #[test]
+    fn send_cmds_splits_full_buffer() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        // 40 commands of 3 bytes without control byte don't fit in a single 64 bytes buffer
+        driver.send_cmds(&[Commands::CommandUnlock; 40]).unwrap();
+        assert_eq!(driver.interface.i2c.count(), 2);
+        assert_eq!(driver.interface.i2c.transfer(0).len(), 1 + 21 * 3);
+        assert_eq!(driver.interface.i2c.transfer(1).len(), 1 + 19 * 3);
+        assert_eq!(&driver.interface.i2c.transfer(1)[0..4], &[0x00, 0xFD, 0x00, 0x12]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn with_buffer_draws_in_borrowed_buffer() {
+        let mut short = [0u8; 64];
+        assert_eq!(SSD1327I2C::with_buffer(MockI2c::new(), 0x3C, 16, 16, &mut short).err(), Some(Error::BufferTooSmall));
+        let mut buffer = [0u8; 128];
+        let mut driver = SSD1327I2C::with_buffer(MockI2c::new(), 0x3C, 16, 16, &mut buffer).unwrap();
+        driver.set_pixel(3, 1, 0x0A);
+        drop(driver);
+        assert_eq!(buffer[8 + 1], 0x0A);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn gamma_corrects_all_but_raw_writes() {
+        use embedded_graphics_core::geometry::Point;
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.set_gamma(true);
+        driver.draw_iter([Pixel(Point::new(0, 0), Gray4::new(1))]).unwrap();
+        driver.set_pixel(1, 0, 1);
+        driver.set_pixel_raw(2, 0, 1);
+        assert_eq!(driver.get_pixel(0, 0), Some(GAMMA_TABLE[1]));
+        assert_eq!(driver.get_pixel(1, 0), Some(GAMMA_TABLE[1]));
+        assert_eq!(driver.get_pixel(2, 0), Some(1));
+        driver.clear(Gray4::new(8));
+        assert_eq!(driver.framebuffer()[0], 0xBB);
+        driver.fill_gray(8);
+        assert_eq!(driver.framebuffer()[0], 0xBB);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn manual_dirty_marks_are_flushed() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        driver.framebuffer_mut()[17] = 0x12;
+        driver.flush().unwrap();
+        assert_eq!(driver.interface.i2c.count(), 0);
+        driver.mark_dirty(2, 1, 3, 2);
+        driver.flush().unwrap();
+        // Columns 1 to 2 of rows 1 to 2
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x15, 1, 2]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0x75, 1, 2]);
+        assert_eq!(driver.interface.i2c.transfer(2), &[0x40, 0x12, 0, 0, 0]);
+        driver.mark_dirty_all();
+        driver.reset_dirty();
+        driver.flush().unwrap();
+        assert_eq!(driver.interface.i2c.count(), 3);
+    }
+
+    #[test]
+    fn send_raw_cmd_adds_control_byte() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.send_raw_cmd(&[0xE3, 0xB4, 0x12]).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0xE3, 0xB4, 0x12]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn flush_region_byte_order_follows_increment() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        // Bytes 0x11 0x12 on row 0 and 0x21 0x22 on row 1
+        driver.framebuffer_mut()[0..2].copy_from_slice(&[0x11, 0x12]);
+        driver.framebuffer_mut()[8..10].copy_from_slice(&[0x21, 0x22]);
+        driver.flush_region(0, 0, 4, 2).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(2), &[0x40, 0x11, 0x12, 0x21, 0x22]);
+        driver.flush_region_with_increment(0, 0, 4, 2, AddressIncrement::Vertical).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(3), &[0x00, 0xA0, 0x55]);
+        assert_eq!(driver.interface.i2c.transfer(4), &[0x00, 0x15, 0, 1]);
+        assert_eq!(driver.interface.i2c.transfer(5), &[0x00, 0x75, 0, 1]);
+        assert_eq!(driver.interface.i2c.transfer(6), &[0x40, 0x11, 0x21, 0x12, 0x22]);
+    }
+
+    #[test]
+    fn brightness_maps_to_contrast() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        for (percent, contrast) in [(0, 0x00), (50, 0x80), (100, 0xFF), (150, 0xFF)] {
+            driver.set_brightness(percent).unwrap();
+            assert_eq!(driver.contrast(), contrast);
+            assert_eq!(driver.brightness(), percent.min(100));
+        }
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0x81, 0x80]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn flush_all_retries_failed_chunks() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        // Attempt 0 is the address window, 1 to 4 the data chunks
+        driver.interface.i2c.fail_attempts(1..2);
+        let error = driver.flush_best_effort().unwrap_err();
+        assert_eq!(error.failed_chunks, 1);
+        assert_eq!(driver.interface.i2c.count(), 4);
+        driver.set_chunk_retry(true);
+        driver.interface.i2c.fail_attempts(6..7);
+        driver.flush_best_effort().unwrap();
+        assert_eq!(driver.interface.i2c.count(), 9);
+        // A chunk failing twice isn't sent a third time
+        driver.interface.i2c.fail_attempts(12..16);
+        assert_eq!(driver.flush_best_effort().unwrap_err().failed_chunks, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn window_draws_with_local_coordinates() {
+        use embedded_graphics_core::geometry::Point;
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        let mut window = driver.window(Rectangle::new(Point::new(4, 2), Size::new(8, 4)));
+        let white = Gray4::new(0xF);
+        window.draw_iter([Pixel(Point::new(0, 0), white), Pixel(Point::new(8, 0), white)]).unwrap();
+        window.flush().unwrap();
+        // Columns 2 to 5 of rows 2 to 5
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x15, 2, 5]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0x75, 2, 5]);
+        assert_eq!(driver.framebuffer()[2 + 2 * 16], 0xF0);
+        assert_eq!(driver.framebuffer().iter().filter(|&&byte| byte != 0).count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn draw_gray8_dithers_rows() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        // Mid-gray alternates between levels 8 and 7, black and white stay exact
+        driver.draw_gray8(0, 0, 8, &[128, 128, 128, 128, 0, 0, 255, 255, 128, 128, 128, 128, 128, 128, 128, 128]);
+        assert_eq!(&driver.framebuffer()[0..4], &[0x87, 0x87, 0x00, 0xFF]);
+        assert_eq!(&driver.framebuffer()[8..12], &[0x78, 0x78, 0x78, 0x78]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn flush_stats_count_frames_and_bytes() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        let mut clock = 1000;
+        driver.set_pixel(0, 0, 0xF);
+        driver.flush_timed(|| { clock += 250; clock }).unwrap();
+        assert_eq!(driver.last_flush_us(), 250);
+        assert_eq!(driver.fps(), Some(4000));
+        driver.flush_all().unwrap();
+        assert_eq!(driver.frame_count(), 2);
+        assert_eq!(driver.flush_bytes_sent(), 1 + 128);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn max_transfer_splits_writes() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.set_max_transfer(16);
+        driver.flush_all().unwrap();
+        let transfers = || driver.interface.i2c.transfers();
+        assert!(transfers().all(|transfer| transfer.len() <= 16));
+        // 4 chunks of 32 bytes, each split in 15 + 15 + 2 bytes after the control byte
+        let data = || transfers().filter(|transfer| transfer[0] == 0x40);
+        assert_eq!(data().count(), 12);
+        assert_eq!(data().map(|transfer| transfer.len() - 1).sum::<usize>(), 128);
+    }
+
+    #[test]
+    fn scroll_vertical_wraps_at_mux_ratio() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.set_mux_ratio(96).unwrap();
+        driver.set_display_offset(16).unwrap();
+        driver.scroll_vertical(40).unwrap();
+        assert_eq!(driver.interface.i2c.transfer(2), &[0x00, 0xA1, 40]);
+        driver.scroll_vertical(40).unwrap();
+        driver.scroll_vertical(16).unwrap();
+        assert_eq!((driver.start_line(), driver.display_offset()), (0, 16));
+        assert_eq!(driver.interface.i2c.transfer(4), &[0x00, 0xA1, 0]);
+    }
+
+    #[test]
+    fn write_raw_at_reports_errors() {
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+
+        let mut driver = SSD1327I2C::new(MockI2c::at_address(0x3D));
+        assert_eq!(driver.write_raw_at(120, 0, 10, 1, &[0; 5]), Err(Error::OutOfBounds));
+        assert_eq!(driver.write_raw_at(0, 127, 4, 2, &[0; 4]), Err(Error::OutOfBounds));
+        // Odd x and width span 3 columns
+        assert_eq!(driver.write_raw_at(1, 0, 4, 2, &[0; 5]), Err(Error::BufferTooSmall));
+        assert_eq!(driver.write_raw_at(1, 0, 4, 2, &[0; 6]), Err(Error::I2c(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))));
+        assert_eq!(driver.interface.i2c.count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn out_of_range_luma_is_masked() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.set_pixel(1, 0, 0x03);
+        driver.set_pixel(0, 0, 0xFF);
+        driver.set_pixel(3, 0, 0xA5);
+        assert_eq!(&driver.framebuffer()[0..2], &[0xF3, 0x05]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn test_patterns_fill_framebuffer() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        driver.test_pattern(TestPattern::Checkerboard).unwrap();
+        assert_eq!(&driver.framebuffer()[0..2], &[0xF0, 0xF0]);
+        assert_eq!(&driver.framebuffer()[16..18], &[0x0F, 0x0F]);
+        driver.test_pattern(TestPattern::Gradient).unwrap();
+        // Two pixels per gray level on a 32 pixels wide display
+        assert_eq!(&driver.framebuffer()[0..16], &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
+        driver.test_pattern(TestPattern::Borders).unwrap();
+        assert!(driver.framebuffer()[0..16].iter().all(|&byte| byte == 0xFF));
+        assert_eq!(&driver.framebuffer()[16..18], &[0xF0, 0x00]);
+        assert_eq!(driver.framebuffer()[31], 0x0F);
+        driver.test_pattern(TestPattern::Stripes).unwrap();
+        assert!(driver.framebuffer()[0..16].iter().all(|&byte| byte == 0xFF));
+        assert!(driver.framebuffer()[16..32].iter().all(|&byte| byte == 0x00));
+        // Every pattern is flushed as a whole
+        assert_eq!(driver.frame_count(), 4);
+        assert_eq!(driver.flush_bytes_sent(), 4 * 256);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn odd_width_rows_are_rounded_up() {
+        assert_eq!(buffer_size(97, 64), 49 * 64);
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 17, 16);
+        driver.set_pixel(16, 0, 0xF);
+        driver.set_pixel(0, 1, 0xA);
+        assert_eq!(&driver.framebuffer()[8..10], &[0xF0, 0xA0]);
+        assert_eq!(driver.get_pixel(16, 0), Some(0xF));
+        driver.flush().unwrap();
+        // The whole rows 0 and 1, 9 columns each
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x15, 0, 8]);
+        assert_eq!(driver.interface.i2c.transfer(2).len(), 1 + 18);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn blend_pixel_alpha_bounds() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.set_pixel(0, 0, 0x4);
+        driver.blend_pixel(0, 0, 0xF, 0);
+        assert_eq!(driver.get_pixel(0, 0), Some(0x4));
+        driver.blend_pixel(0, 0, 0xF, 15);
+        assert_eq!(driver.get_pixel(0, 0), Some(0xF));
+        // Half way between 15 and 0
+        driver.blend_pixel(0, 0, 0x0, 8);
+        assert_eq!(driver.get_pixel(0, 0), Some(0x7));
+    }
+
+    #[test]
+    fn geometry_getters() {
+        let driver = SSD1327I2C::<_, { buffer_size(97, 64) }>::with_wh(MockI2c::new(), 97, 64);
+        assert_eq!((driver.width(), driver.height(), driver.byte_width()), (97, 64, 49));
+    }
+
+    #[test]
+    fn init_minimal_sends_essential_commands() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        driver.init_minimal().unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[
+            0x00, 0xFD, 0x00, 0x12, 0xAE, 0x15, 0x00, 0x3F, 0x75, 0x00, 0x7F, 0xA0, 0x51, 0xA4, 0xAF,
+        ]);
+        assert!(driver.is_on());
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn text_with_background_is_packed() {
+        use embedded_graphics::{
+            mono_font::{ascii::FONT_6X10, MonoTextStyleBuilder},
+            prelude::*,
+            text::{Baseline, Text},
+        };
+
+        // Counts the pixels drawn one by one instead of by area
+        struct Counting<'a, T>(&'a mut T, usize);
+        impl<T: DrawTarget<Color = Gray4> + OriginDimensions> DrawTarget for Counting<'_, T> {
+            type Color = Gray4;
+            type Error = T::Error;
+            fn draw_iter<I: IntoIterator<Item = Pixel<Gray4>>>(&mut self, pixels: I) -> Result<(), Self::Error> {
+                let mut count = 0;
+                self.0.draw_iter(pixels.into_iter().inspect(|_| count += 1))?;
+                self.1 += count;
+                Ok(())
+            }
+            fn fill_contiguous<I: IntoIterator<Item = Gray4>>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error> {
+                self.0.fill_contiguous(area, colors)
+            }
+            fn fill_solid(&mut self, area: &Rectangle, color: Gray4) -> Result<(), Self::Error> {
+                self.0.fill_solid(area, color)
+            }
+        }
+        impl<T: OriginDimensions> OriginDimensions for Counting<'_, T> {
+            fn size(&self) -> Size {
+                self.0.size()
+            }
+        }
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 64, 16);
+        let style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(Gray4::new(0xF))
+            .background_color(Gray4::new(0x3))
+            .build();
+        let mut counting = Counting(&mut driver, 0);
+        Text::with_baseline("Menu 1", Point::new(1, 0), style, Baseline::Top).draw(&mut counting).unwrap();
+        assert_eq!(counting.1, 0);
+        // 6 glyphs of 6x10 pixels from x = 1, the background fills every pixel of the cells
+        let rows = driver.framebuffer().chunks(32).take(10);
+        for row in rows {
+            assert_eq!(row[0] & 0xF0, 0x00);
+            assert!(row[1..18].iter().all(|&byte| byte & 0xF0 != 0 && byte & 0x0F != 0));
+            assert_eq!(row[18] & 0x0F, 0x00);
+        }
+        assert!(driver.framebuffer()[10 * 32..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn flush_interlaced_covers_every_row_once() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.flush_interlaced().unwrap();
+        let rows: heapless::Vec<u8, 16> = driver.interface.i2c.transfers()
+            .filter(|transfer| transfer[1] == 0x75)
+            .map(|transfer| {
+                assert_eq!(transfer[2], transfer[3]);
+                transfer[2]
+            })
+            .collect();
+        assert_eq!(&rows[..], &[0, 2, 4, 6, 8, 10, 12, 14, 1, 3, 5, 7, 9, 11, 13, 15]);
+        assert_eq!(driver.flush_bytes_sent(), 128);
+    }
+
+    #[test]
+    fn function_b_composes_register_byte() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.set_function_b(true, true).unwrap();
+        assert_eq!(driver.function_b(), 0x63);
+        driver.set_function_b(false, false).unwrap();
+        assert_eq!(driver.function_b(), 0x60);
+        let mut transfers = driver.interface.i2c.transfers();
+        assert_eq!(transfers.next(), Some(&[0x00, 0xD5, 0x63][..]));
+        assert_eq!(transfers.next(), Some(&[0x00, 0xD5, 0x60][..]));
+    }
+
+    #[test]
+    fn dimensions_above_max_are_rejected() {
+        assert_eq!(check_geometry::<{ buffer_size(130, 128) }>(130, 128), Err(InvalidGeometry));
+        assert_eq!(
+            SSD1327I2C::<_, { buffer_size(130, 128) }>::try_with_addr_wh(MockI2c::new(), 0x3C, 130, 128).err(),
+            Some(Error::InvalidGeometry)
+        );
+        assert!(check_geometry::<{ buffer_size(MAX_WIDTH, MAX_HEIGHT) }>(MAX_WIDTH, MAX_HEIGHT).is_ok());
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn index_returns_packed_byte() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.set_pixel(4, 3, 0x0A);
+        driver.set_pixel(5, 3, 0x05);
+        assert_eq!(driver[(4, 3)], 0xA5);
+        assert_eq!(driver[(5, 3)], 0xA5);
+        driver.set_pixel(5, 3, 0x00);
+        assert_eq!(driver[(4, 3)], 0xA0);
+        assert_eq!(driver[(6, 3)], 0x00);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    #[should_panic]
+    fn index_out_of_bounds_panics() {
+        let driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        let _ = driver[(16, 0)];
+    }
+
+    #[test]
+    fn second_precharge_is_clamped() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.set_second_precharge(0).unwrap();
+        driver.set_second_precharge(20).unwrap();
+        driver.set_second_precharge(8).unwrap();
+        let mut transfers = driver.interface.i2c.transfers();
+        assert_eq!(transfers.next(), Some(&[0x00, 0xB6, 0x01][..]));
+        assert_eq!(transfers.next(), Some(&[0x00, 0xB6, 0x0F][..]));
+        assert_eq!(transfers.next(), Some(&[0x00, 0xB6, 0x08][..]));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn flush_byte_count_matches_flush_all() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        let recorded = |i2c: &MockI2c| i2c.transfers().map(<[u8]>::len).sum::<usize>();
+        let expected = driver.flush_byte_count();
+        assert_eq!(expected, 8192 + 256 + 7);
+        driver.flush_all().unwrap();
+        assert_eq!(recorded(&driver.interface.i2c), expected);
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 30, 16);
+        driver.set_addressing_mode(AddressIncrement::Vertical).unwrap();
+        driver.set_max_transfer(16);
+        driver.set_chunk_len(20);
+        driver.set_command_control_byte(0x80);
+        let before = recorded(&driver.interface.i2c);
+        let expected = driver.flush_byte_count();
+        driver.flush_all().unwrap();
+        assert_eq!(recorded(&driver.interface.i2c) - before, expected);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn flush_diff_sends_changed_bytes_only() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        let mut shadow = [0u8; buffer_size(128, 128)];
+        driver.set_pixel(11, 20, 0x0F);
+        driver.flush_diff(&mut shadow).unwrap();
+        let i2c = &driver.interface.i2c;
+        assert_eq!(i2c.count(), 3);
+        assert_eq!(i2c.transfer(0), &[0x00, 0x15, 5, 5]);
+        assert_eq!(i2c.transfer(1), &[0x00, 0x75, 20, 20]);
+        assert_eq!(i2c.transfer(2), &[0x40, 0x0F]);
+        assert_eq!(shadow[5 + 20 * 64], 0x0F);
+
+        // Nothing changed since
+        driver.flush_diff(&mut shadow).unwrap();
+        assert_eq!(driver.interface.i2c.count(), 3);
+        assert_eq!(driver.flush_diff(&mut [0u8; 10]), Err(Error::BufferTooSmall));
+    }
+
+    #[test]
+    fn transfers_group_control_byte_and_bytes() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.init().unwrap();
+        driver.send_data(&[0xFF; 2 * DEFAULT_CHUNK_LEN]).unwrap();
+        let i2c = &driver.interface.i2c;
+        // Control byte and bytes of every transfer share a transaction, merged in a single write
+        for index in 0..i2c.count() {
+            assert_eq!(i2c.operations(index), 2);
+        }
+        assert_eq!(i2c.transfer(i2c.count() - 1)[0], 0x40);
+        assert_eq!(i2c.transfer(i2c.count() - 1).len(), DEFAULT_CHUNK_LEN + 1);
+    }
+
+    #[test]
+    fn com_split_flips_remap_bit() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        assert!(driver.com_split());
+        driver.set_com_split(false).unwrap();
+        assert_eq!(driver.remap(), 0x11);
+        assert!(!driver.com_split());
+        driver.set_com_split(true).unwrap();
+        assert_eq!(driver.remap(), 0x51);
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0xA0, 0x11]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0xA0, 0x51]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn with_buffer_rejects_one_byte_short_buffer() {
+        let mut short = [0u8; buffer_size(32, 32) - 1];
+        let driver = SSD1327I2C::with_buffer(MockI2c::new(), 0x3C, 32, 32, &mut short);
+        assert_eq!(driver.err(), Some(Error::BufferTooSmall));
+        let driver = SSD1327I2C::with_addr_wh_buffer_checked(MockI2c::new(), 0x3C, 32, 32, &mut short);
+        assert_eq!(driver.err(), Some(Error::BufferTooSmall));
+        let mut buffer = [0u8; buffer_size(32, 32)];
+        assert!(SSD1327I2C::with_buffer(MockI2c::new(), 0x3C, 32, 32, &mut buffer).is_ok());
+        assert!(SSD1327I2C::with_addr_wh_buffer_checked(MockI2c::new(), 0x3C, 32, 32, &mut buffer).is_ok());
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn full_screen_fill_uses_clear() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{PrimitiveStyle, Rectangle},
+        };
+
+        let mut cleared = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        cleared.clear(Gray4::new(0x9));
+        // Rotated by 90 degrees a full screen rectangle used to be drawn pixel by pixel (512 nibble writes),
+        // it is now a single fill of the 256 bytes
+        let mut drawn = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        drawn.set_rotation(Rotation::Deg90).unwrap();
+        Rectangle::new(Point::new(-4, -4), Size::new(64, 64))
+            .into_styled(PrimitiveStyle::with_fill(Gray4::new(0x9)))
+            .draw(&mut drawn)
+            .unwrap();
+        assert_eq!(drawn.framebuffer(), cleared.framebuffer());
+        let mut trait_cleared = build_ssd1327_i2c!(MockI2c::new(), 32, 16);
+        DrawTarget::clear(&mut trait_cleared, Gray4::new(0x9)).unwrap();
+        assert_eq!(trait_cleared.framebuffer(), cleared.framebuffer());
+        assert!(trait_cleared.framebuffer().iter().all(|&byte| byte == 0x99));
+    }
+
+    #[cfg(feature = "text")]
+    #[test]
+    fn status_line_tick_wraps_at_text_end() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 64, 16);
+        let mut status = StatusLine::<32>::new();
+        status.set_text("Hi");
+        status.tick(&mut driver);
+        assert_eq!(status.offset(), 0);
+        // 'H' drawn on the bottom line
+        assert_eq!(driver.get_pixel(0, 8), Some(0x0F));
+
+        // 12 characters, 72 pixels wide, scroll over 72 pixels and the space following them
+        status.set_text("ABCDEFGHIJKL");
+        for offset in 1..78 {
+            status.tick(&mut driver);
+            assert_eq!(status.offset(), offset);
+        }
+        status.tick(&mut driver);
+        assert_eq!(status.offset(), 0);
+
+        let mut short = StatusLine::<4>::new();
+        short.set_text("ABCDEFGH");
+        assert_eq!(short.text(), "ABCD");
+    }
+
+    #[test]
+    fn window_pixels_converts_to_column_addresses() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.window_pixels(0, 0, 128, 128).unwrap();
+        driver.window_pixels(3, 10, 4, 2).unwrap();
+        driver.window_pixels(120, 126, 20, 20).unwrap();
+        driver.window_pixels(10, 10, 0, 5).unwrap();
+        let i2c = &driver.interface.i2c;
+        assert_eq!(i2c.count(), 3);
+        assert_eq!(i2c.transfer(0), &[0x00, 0x15, 0, 63, 0x75, 0, 127]);
+        // Pixels 3 to 6 are in the columns of pixels 2-3 to 6-7
+        assert_eq!(i2c.transfer(1), &[0x00, 0x15, 1, 3, 0x75, 10, 11]);
+        assert_eq!(i2c.transfer(2), &[0x00, 0x15, 60, 63, 0x75, 126, 127]);
+    }
+
+    #[test]
+    fn assume_initialized_sends_nothing() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.set_contrast(0x20).unwrap();
+        assert!(!driver.is_on());
+        driver.assume_initialized();
+        assert!(driver.is_on());
+        assert_eq!(driver.contrast(), 0x20);
+        assert_eq!(driver.remap(), 0x51);
+        assert_eq!(driver.mux_ratio(), DEFAULT_MUX_RATIO + 1);
+        assert_eq!(driver.interface.i2c.count(), 1);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn fill_solid_writes_whole_rows() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{PrimitiveStyle, Rectangle},
+        };
+
+        let buffer = CountingBuffer([0; buffer_size(128, 128)], 0);
+        let mut driver = SSD1327::<_, 0, _, _>::from_parts(I2cInterface::new(MockI2c::new(), 0x3C), 128, 128, buffer);
+        let fill = PrimitiveStyle::with_fill(Gray4::new(0x7));
+        Rectangle::new(Point::zero(), Size::new(128, 128)).into_styled(fill).draw(&mut driver).unwrap();
+        assert_eq!(driver.framebuffer.1, 1);
+        assert!(driver.framebuffer().iter().all(|&byte| byte == 0x77));
+
+        // From an odd x, a single fill per row and the lone left pixel instead of 127 pixels per row
+        driver.framebuffer.1 = 0;
+        let fill = PrimitiveStyle::with_fill(Gray4::new(0x2));
+        Rectangle::new(Point::new(1, 1), Size::new(127, 127)).into_styled(fill).draw(&mut driver).unwrap();
+        assert_eq!(driver.framebuffer.1, 2 * 127);
+        assert_eq!(driver.framebuffer()[0], 0x77);
+        assert_eq!(driver.framebuffer()[64], 0x72);
+        assert!(driver.framebuffer()[65..128].iter().all(|&byte| byte == 0x22));
+    }
+
+    #[test]
+    fn vcomh_levels_map_to_register_bytes() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        assert_eq!(driver.vcomh(), 0x05);
+        for (index, (level, byte)) in [(VcomhLevel::Vcc072, 0x00), (VcomhLevel::Vcc082, 0x05), (VcomhLevel::Vcc086, 0x07)]
+            .into_iter()
+            .enumerate()
+        {
+            driver.set_vcomh(level).unwrap();
+            assert_eq!(driver.vcomh(), byte);
+            assert_eq!(driver.interface.i2c.transfer(index), &[0x00, 0xBE, byte]);
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn flush_all_stops_at_first_error() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        // Attempt 0 is the address window, the first data chunk fails
+        driver.interface.i2c.fail_attempts(1..usize::MAX);
+        let error = driver.flush_all().unwrap_err();
+        assert_eq!(error.failed_chunks, 1);
+        assert_eq!(driver.interface.i2c.attempts(), 2);
+        // Disconnected display, the address window fails
+        assert_eq!(driver.flush_all().unwrap_err().failed_chunks, 0);
+        assert_eq!(driver.interface.i2c.attempts(), 3);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn framebuffer_reads_back_as_image_raw() {
+        use embedded_graphics::{image::{GetPixel, ImageRaw}, prelude::*};
+
+        // Odd width, the last byte of each row only holds one pixel
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 17, 16);
+        for y in 0..16 {
+            for x in 0..17 {
+                driver.set_pixel(x, y, (x + 3 * y) % 16);
+            }
+        }
+        let image = ImageRaw::<Gray4>::new(driver.framebuffer(), driver.width() as u32);
+        assert_eq!(image.size(), Size::new(17, 16));
+        for y in 0..16 {
+            for x in 0..17 {
+                let pixel = image.pixel(Point::new(x as i32, y as i32)).map(|gray| gray.luma());
+                assert_eq!(pixel, driver.get_pixel(x, y));
+            }
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn offsets_shift_address_windows() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 64, 64).with_offset(8, 32);
+        assert_eq!((driver.col_offset(), driver.row_offset()), (8, 32));
+        driver.init().unwrap();
+        assert_eq!(&driver.interface.i2c.transfer(0)[5..11], &[0x15, 8, 39, 0x75, 32, 95]);
+        driver.flush_all().unwrap();
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0x15, 8, 39, 0x75, 32, 95]);
+        driver.write_raw_at(4, 2, 2, 1, &[0xFF]).unwrap();
+        let count = driver.interface.i2c.count();
+        assert_eq!(driver.interface.i2c.transfer(count - 2), &[0x00, 0x15, 10, 10, 0x75, 34, 34]);
+        driver.set_pixel(0, 0, 0x0F);
+        driver.flush().unwrap();
+        let count = driver.interface.i2c.count();
+        assert_eq!(driver.interface.i2c.transfer(count - 3), &[0x00, 0x15, 8, 8]);
+        assert_eq!(driver.interface.i2c.transfer(count - 2), &[0x00, 0x75, 32, 32]);
+
+        // Clamped to the GDDRAM
+        let driver = build_ssd1327_i2c!(MockI2c::new(), 64, 64).with_offset(40, 100);
+        assert_eq!((driver.col_offset(), driver.row_offset()), (32, 64));
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[test]
+    fn fill_gray_fills_framebuffer() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 17, 16);
+        driver.fill_gray(0x1A);
+        assert!(driver.framebuffer().iter().all(|&byte| byte == 0xAA));
+        driver.flush().unwrap();
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x15, 0, 8]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0x75, 0, 15]);
+    }
+
+    #[test]
+    fn send_data_splits_long_data() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.set_chunk_len(100);
+        driver.set_max_transfer(48);
+        let data: [u8; 300] = core::array::from_fn(|index| index as u8);
+        driver.send_data(&data).unwrap();
+        let mut payload: heapless::Vec<u8, 300> = heapless::Vec::new();
+        for transfer in driver.interface.i2c.transfers() {
+            assert!(transfer.len() <= 48);
+            assert_eq!(transfer[0], 0x40);
+            payload.extend_from_slice(&transfer[1..]).unwrap();
+        }
+        assert_eq!(&payload[..], &data[..]);
+        // 3 chunks of 100 bytes, each in 3 transfers of 47, 47 and 6 bytes
+        assert_eq!(driver.interface.i2c.count(), 9);
+    }
+
+    #[test]
+    fn lux_maps_to_contrast() {
+        assert_eq!(contrast_for_lux(0), 0x10);
+        assert_eq!(contrast_for_lux(5), 0x20);
+        assert_eq!(contrast_for_lux(100), 0x70);
+        assert_eq!(contrast_for_lux(550), 0x98);
+        assert_eq!(contrast_for_lux(10000), 0xFF);
+        assert_eq!(contrast_for_lux(u16::MAX), 0xFF);
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.set_contrast_from_lux(1000).unwrap();
+        assert_eq!(driver.contrast(), 0xC0);
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x81, 0xC0]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn gray8_keeps_high_nibble() {
+        use embedded_graphics::{pixelcolor::Gray8, prelude::*};
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        let mut target = driver.gray8();
+        Pixel(Point::new(0, 0), Gray8::new(0xF0)).draw(&mut target).unwrap();
+        Pixel(Point::new(1, 0), Gray8::new(0x7F)).draw(&mut target).unwrap();
+        assert_eq!(driver.get_pixel(0, 0), Some(0xF));
+        assert_eq!(driver.get_pixel(1, 0), Some(0x7));
+        // The nearest level conversion rounds 0xF0 down
+        Pixel(Point::new(2, 0), Gray8::new(0xF0)).draw(&mut driver.color_converted()).unwrap();
+        assert_eq!(driver.get_pixel(2, 0), Some(0xE));
+    }
+
+    #[test]
+    fn phase_length_composes_register_byte() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.set_phase_length(0x1, 0x5).unwrap();
+        assert_eq!(driver.phase_length(), 0x51);
+        driver.set_phase_length(0, 20).unwrap();
+        assert_eq!(driver.phase_length(), 0xF1);
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0xB1, 0x51]);
+        assert_eq!(driver.interface.i2c.transfer(1), &[0x00, 0xB1, 0xF1]);
+        driver.init().unwrap();
+        assert_eq!(driver.phase_length(), 0xF1);
+    }
+
+    #[cfg(all(feature = "async", feature = "framebuffer"))]
+    #[test]
+    fn async_flush_stops_at_first_error() {
+        use crate::mock::block_on;
+
+        // Failing address window
+        let mut i2c = MockI2c::new();
+        i2c.fail_attempts(0..1);
+        let mut driver = SSD1327I2CAsync::<_, { buffer_size(16, 16) }>::with_wh(i2c, 16, 16);
+        assert!(block_on(driver.flush()).is_err());
+        let i2c = driver.into_inner();
+        assert_eq!(i2c.attempts(), 1);
+        assert_eq!(i2c.count(), 0);
+
+        // Failing first data chunk, after the address window
+        let mut i2c = MockI2c::new();
+        i2c.fail_attempts(1..2);
+        let mut driver = SSD1327I2CAsync::<_, { buffer_size(128, 128) }>::new(i2c);
+        assert!(block_on(driver.flush()).is_err());
+        let i2c = driver.into_inner();
+        assert_eq!(i2c.attempts(), 2);
+        assert_eq!(i2c.count(), 1);
+    }
+
+    #[cfg(all(feature = "async", feature = "framebuffer"))]
+    #[test]
+    fn async_offsets_shift_address_windows() {
+        use crate::mock::block_on;
+
+        let mut driver = SSD1327I2CAsync::<_, { buffer_size(64, 64) }>::with_wh(MockI2c::new(), 64, 64).with_offset(8, 32);
+        assert_eq!((driver.col_offset(), driver.row_offset()), (8, 32));
+        block_on(driver.init()).unwrap();
+        block_on(driver.flush()).unwrap();
+        let i2c = driver.into_inner();
+        assert_eq!(&i2c.transfer(0)[5..11], &[0x15, 8, 39, 0x75, 32, 95]);
+        assert_eq!(i2c.transfer(1), &[0x00, 0x15, 8, 39, 0x75, 32, 95]);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_send_data_uses_chunk_len() {
+        use crate::mock::block_on;
+
+        let mut driver = SSD1327I2CAsync::new(MockI2c::new());
+        assert_eq!(driver.chunk_len(), DEFAULT_CHUNK_LEN);
+        driver.set_chunk_len(20);
+        block_on(driver.send_data(&[0xAB; 50])).unwrap();
+        block_on(driver.send_cmd(Commands::DisplayON)).unwrap();
+        let i2c = driver.into_inner();
+        assert_eq!(i2c.count(), 4);
+        assert_eq!(i2c.transfer(0).len(), 21);
+        assert_eq!(i2c.transfer(2)[0], 0x40);
+        assert_eq!(i2c.transfer(2).len(), 11);
+        // Control byte and bytes sent without copy, like the blocking driver
+        assert_eq!(i2c.operations(2), 2);
+        assert_eq!(i2c.transfer(3), &[0x00, 0xAF]);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn clear_fills_both_nibbles() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 96, 64);
+        for luma in [0x0, 0x5, 0xA, 0xF] {
+            driver.clear(Gray4::new(luma));
+            assert!(driver.framebuffer().iter().all(|&byte| byte == (luma << 4) | luma));
+        }
+    }
+
+    #[test]
+    fn send_data_transfers_per_payload_len() {
+        // Payload length and expected number of transfers with the default chunk length of 32 bytes
+        for (len, transfers) in [(0, 0), (3, 1), (8, 1), (64, 2)] {
+            let mut driver = SSD1327I2C::new(MockI2c::new());
+            driver.send_data(&[0xA5; 64][..len]).unwrap();
+            let i2c = &driver.interface.i2c;
+            assert_eq!(i2c.count(), transfers);
+            assert!(i2c.transfers().all(|transfer| transfer[0] == 0x40));
+            assert_eq!(i2c.transfers().map(|transfer| transfer.len() - 1).sum::<usize>(), len);
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[test]
+    fn flush_all_sends_framebuffer_for_any_chunk_len() {
+        for chunk_len in [1, 7, DEFAULT_CHUNK_LEN, 100, MAX_CHUNK_LEN] {
+            let mut driver = build_ssd1327_i2c!(MockI2c::new(), 32, 32);
+            driver.set_chunk_len(chunk_len);
+            driver.flush_all().unwrap();
+            let i2c = &driver.interface.i2c;
+            // Address window first, then the data transfers
+            let data_len: usize = i2c.transfers().skip(1).map(|transfer| transfer.len() - 1).sum();
+            assert_eq!(data_len, driver.framebuffer().len());
+            assert_eq!(i2c.count() - 1, driver.framebuffer().len().div_ceil(chunk_len));
+        }
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn deg90_origin_lands_in_last_column() {
+        use embedded_graphics::prelude::*;
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 96);
+        driver.set_rotation(Rotation::Deg90).unwrap();
+        Pixel(Point::new(0, 0), Gray4::new(0xF)).draw(&mut driver).unwrap();
+        // (0, 0) is the last pixel of the first framebuffer row, in the low nibble of byte 63
+        assert_eq!(driver.framebuffer()[63], 0x0F);
+        assert_eq!(driver.framebuffer().iter().filter(|&&byte| byte != 0).count(), 1);
+        Pixel(Point::new(0, 1), Gray4::new(0xA)).draw(&mut driver).unwrap();
+        assert_eq!(driver.framebuffer()[63], 0xAF);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn fill_64x64_writes_once_per_row() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{PrimitiveStyle, Rectangle},
+        };
+
+        let buffer = CountingBuffer([0; buffer_size(128, 128)], 0);
+        let mut driver = SSD1327::<_, 0, _, _>::from_parts(I2cInterface::new(MockI2c::new(), 0x3C), 128, 128, buffer);
+        let fill = PrimitiveStyle::with_fill(Gray4::new(0x9));
+        Rectangle::new(Point::new(32, 32), Size::new(64, 64)).into_styled(fill).draw(&mut driver).unwrap();
+        // A single write per row instead of 4096 pixel writes
+        assert_eq!(driver.framebuffer.1, 64);
+        assert!(driver.framebuffer()[32 * 64 + 16..32 * 64 + 48].iter().all(|&byte| byte == 0x99));
+        assert_eq!(driver.framebuffer()[32 * 64 + 15], 0x00);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[test]
+    fn flush_sends_dirty_region_only() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        for y in 32..48 {
+            for x in 32..48 {
+                driver.set_pixel(x, y, 0xC);
+            }
+        }
+        driver.flush().unwrap();
+        let i2c = &driver.interface.i2c;
+        // Address window of columns 16-23 and rows 32-47, then 8 bytes per row in transfers of 32 data bytes
+        assert_eq!(i2c.transfer(0), &[0x00, 0x15, 16, 23]);
+        assert_eq!(i2c.transfer(1), &[0x00, 0x75, 32, 47]);
+        assert_eq!(i2c.count(), 2 + 4);
+        assert_eq!(i2c.transfers().map(|transfer| transfer.len()).sum::<usize>(), 2 * 4 + 16 * 8 + 4);
+        assert!(i2c.transfers().skip(2).all(|transfer| transfer[1..].iter().all(|&byte| byte == 0xCC)));
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[test]
+    fn smaller_panels_flush_whole_framebuffer() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 96, 64);
+        assert_eq!(driver.framebuffer().len(), 48 * 64);
+        driver.flush_all().unwrap();
+        let data_len: usize = driver.interface.i2c.transfers().skip(1).map(|transfer| transfer.len() - 1).sum();
+        assert_eq!(data_len, 48 * 64);
+
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 96);
+        assert_eq!(driver.framebuffer().len(), 64 * 96);
+        driver.flush_all().unwrap();
+        let data_len: usize = driver.interface.i2c.transfers().skip(1).map(|transfer| transfer.len() - 1).sum();
+        assert_eq!(data_len, 64 * 96);
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn build_macro_sizes_display() {
+        assert_eq!(build_ssd1327_i2c!(MockI2c::new(), 128, 128).size(), Size::new(128, 128));
+        assert_eq!(build_ssd1327_i2c!(MockI2c::new(), 0x3D, 96, 64).size(), Size::new(96, 64));
+    }
+
+    #[test]
+    fn builder_infers_framebuffer_size() {
+        let driver = SSD1327Builder::new(MockI2c::new())
+            .address(0x3D)
+            .rotation(Rotation::Deg180)
+            .contrast(0x20)
+            .build_and_init()
+            .unwrap();
+        assert_eq!((driver.width(), driver.height()), (PANEL_WIDTH, PANEL_HEIGHT));
+        assert_eq!(driver.slave_address(), 0x3D);
+        assert_eq!(driver.rotation(), Rotation::Deg180);
+        // Init sequence with the contrast and the flipped remap of the builder
+        let init = driver.interface.i2c.transfer(0);
+        assert_eq!(&init[11..15], &[0x81, 0x20, 0xA0, DEFAULT_REMAP ^ FLIP_REMAP_BITS]);
+
+        let driver = SSD1327Builder::<_, { buffer_size(96, 64) }>::with_size(MockI2c::new(), 96, 64).build();
+        assert_eq!((driver.width(), driver.height()), (96, 64));
+    }
+
+    #[test]
+    fn copy_transfers_send_single_writes() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 128, 128);
+        driver.set_copy_transfers(true);
+        driver.send_cmd(Commands::DisplayON).unwrap();
+        driver.send_data(&[0xAB; DEFAULT_CHUNK_LEN + 3]).unwrap();
+        let i2c = &driver.interface.i2c;
+        // Same transfers as the merged write operations, each written at once
+        assert_eq!(i2c.count(), 3);
+        assert_eq!(i2c.transfer(0), &[0x00, 0xAF]);
+        assert_eq!(i2c.transfer(1).len(), DEFAULT_CHUNK_LEN + 1);
+        assert_eq!(i2c.transfer(2), &[0x40, 0xAB, 0xAB, 0xAB]);
+        assert!((0..3).all(|index| i2c.operations(index) == 1));
+    }
+
+    #[cfg(feature = "graphics")]
+    #[test]
+    fn multi_display_applies_panel_gamma() {
+        use embedded_graphics_core::geometry::Point;
+
+        let left = build_ssd1327_i2c!(MockI2c::new(), 0x3C, 16, 16);
+        let mut right = build_ssd1327_i2c!(MockI2c::new(), 0x3D, 16, 16);
+        right.set_gamma(true);
+        let mut multi = MultiDisplay::new([left, right]).unwrap();
+        let gray = Gray4::new(0x4);
+        multi.draw_iter([Pixel(Point::new(0, 0), gray), Pixel(Point::new(16, 0), gray)]).unwrap();
+        let [left, mut right] = multi.release();
+        assert_eq!(left.get_pixel(0, 0), Some(0x4));
+        assert_eq!(right.get_pixel(0, 0), Some(GAMMA_TABLE[0x4]));
+        // Same gray as drawing on the panel alone
+        right.draw_iter([Pixel(Point::new(1, 0), gray)]).unwrap();
+        assert_eq!(right.get_pixel(1, 0), right.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn fade_to_without_steps_does_not_wait() {
+        let mut driver = SSD1327I2C::new(MockI2c::new());
+        let mut delay = MockDelay(0);
+        driver.fade_to(0x30, 0, &mut delay, 10).unwrap();
+        assert_eq!(driver.contrast(), 0x30);
+        assert_eq!(driver.interface.i2c.count(), 1);
+        assert_eq!(driver.interface.i2c.transfer(0), &[0x00, 0x81, 0x30]);
+        assert_eq!(delay.0, 0);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[test]
+    fn flush_best_effort_fails_without_window() {
+        let mut driver = build_ssd1327_i2c!(MockI2c::new(), 16, 16);
+        driver.set_pixel(0, 0, 0xF);
+        // Attempt 0 is the address window, the data chunks would succeed
+        driver.interface.i2c.fail_attempts(0..1);
+        let error = driver.flush_best_effort().unwrap_err();
+        assert_eq!(error.failed_chunks, 0);
+        assert_eq!(driver.interface.i2c.attempts(), 1);
+        assert_eq!(driver.frame_count(), 0);
+        // Still dirty, the next flush sends the pixel
+        driver.flush().unwrap();
+        assert_eq!(driver.interface.i2c.transfers().last(), Some(&[0x40, 0xF0][..]));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_init_matches_blocking_init() {
+        use crate::mock::block_on;
+
+        let cfg = DisplayConfig { contrast: 0x20, remap: 0x42, ..DisplayConfig::default() };
+        let mut blocking = SSD1327I2C::new(MockI2c::new());
+        blocking.init_with_config(cfg).unwrap();
+        let mut driver = SSD1327I2CAsync::new(MockI2c::new());
+        block_on(driver.init_with_config(cfg)).unwrap();
+        let i2c = driver.into_inner();
+        // A single batched transfer, byte for byte the blocking one
+        assert_eq!(i2c.count(), 1);
+        assert_eq!(i2c.transfer(0), blocking.interface.i2c.transfer(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "check_geometry")]
+    fn zero_height_panics_with_clear_message() {
+        let _ = SSD1327I2C::<_, 0>::with_addr_wh(MockI2c::new(), 0x3C, 128, 0);
+    }
+}
@@ -0,0 +1,190 @@
+//! Mock peripherals recording every transfer, used by the unit tests
+
+use core::cell::Cell;
+use core::convert::Infallible;
+use core::ops::Range;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, OutputPin};
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use embedded_hal::spi::{self, SpiDevice};
+use heapless::Vec;
+
+/// Mock I2C peripheral recording the bytes of every write transfer
+///
+/// Like a real bus, the adjacent write operations of a transaction are merged into a single transfer.
+pub(crate) struct MockI2c {
+    bytes: Vec<u8, 32768>,
+    lens: Vec<usize, 1024>,
+    operations: Vec<usize, 1024>,
+    address: Option<u8>,
+    attempts: usize,
+    failures: Range<usize>,
+}
+
+impl MockI2c {
+    /// Create a new mock without any recorded transfer
+    pub(crate) fn new() -> Self {
+        MockI2c {
+            bytes: Vec::new(),
+            lens: Vec::new(),
+            operations: Vec::new(),
+            address: None,
+            attempts: 0,
+            failures: 0..0,
+        }
+    }
+
+    /// Create a new mock answering only at the given slave address, other addresses aren't acknowledged
+    pub(crate) fn at_address(address: u8) -> Self {
+        MockI2c {
+            address: Some(address),
+            ..MockI2c::new()
+        }
+    }
+
+    /// Make the write attempts in the given range fail, attempts are counted from the creation of the mock
+    #[cfg_attr(not(feature = "graphics"), allow(dead_code))]
+    pub(crate) fn fail_attempts(&mut self, failures: Range<usize>) {
+        self.failures = failures;
+    }
+
+    /// Recorded transfers, in order
+    pub(crate) fn transfers(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.lens.iter().scan(0, |start, &len| {
+            let transfer = &self.bytes[*start..*start + len];
+            *start += len;
+            Some(transfer)
+        })
+    }
+
+    /// Recorded transfer at the given index
+    pub(crate) fn transfer(&self, index: usize) -> &[u8] {
+        self.transfers().nth(index).unwrap()
+    }
+
+    /// Number of transactions attempted, failed ones included
+    #[cfg_attr(not(feature = "graphics"), allow(dead_code))]
+    pub(crate) fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Number of write operations merged in the recorded transfer at the given index
+    pub(crate) fn operations(&self, index: usize) -> usize {
+        self.operations[index]
+    }
+
+    /// Number of recorded transfers
+    pub(crate) fn count(&self) -> usize {
+        self.lens.len()
+    }
+}
+
+impl ErrorType for MockI2c {
+    type Error = ErrorKind;
+}
+
+impl I2c for MockI2c {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        if self.address.is_some_and(|acked| acked != address) {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        self.attempts += 1;
+        if self.failures.contains(&(self.attempts - 1)) {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data));
+        }
+        let (mut len, mut count) = (0, 0);
+        for operation in operations {
+            if let Operation::Write(bytes) = operation {
+                self.bytes.extend_from_slice(bytes).unwrap();
+                len += bytes.len();
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.lens.push(len).unwrap();
+            self.operations.push(count).unwrap();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for MockI2c {
+    async fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+/// Run a future to completion, the mocks never return `Poll::Pending`
+#[cfg(feature = "async")]
+pub(crate) fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let mut context = core::task::Context::from_waker(core::task::Waker::noop());
+    loop {
+        if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// Mock delay adding up the requested nanoseconds
+pub(crate) struct MockDelay(pub(crate) u64);
+
+impl DelayNs for MockDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0 += ns as u64;
+    }
+}
+
+/// Mock SPI device recording the bytes of every write along with the D/C pin level
+pub(crate) struct MockSpi<'a> {
+    dc: &'a Cell<bool>,
+    writes: Vec<(bool, Vec<u8, 64>), 16>,
+}
+
+impl<'a> MockSpi<'a> {
+    /// Create a new mock reading the level of the given D/C pin
+    pub(crate) fn new(dc: &'a Cell<bool>) -> Self {
+        MockSpi { dc, writes: Vec::new() }
+    }
+
+    /// Recorded D/C level and bytes of the write at the given index
+    pub(crate) fn write(&self, index: usize) -> (bool, &[u8]) {
+        let (dc, bytes) = &self.writes[index];
+        (*dc, bytes)
+    }
+}
+
+impl spi::ErrorType for MockSpi<'_> {
+    type Error = spi::ErrorKind;
+}
+
+impl SpiDevice for MockSpi<'_> {
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), Self::Error> {
+        for operation in operations {
+            if let spi::Operation::Write(bytes) = operation {
+                self.writes.push((self.dc.get(), Vec::from_slice(bytes).unwrap())).unwrap();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mock D/C pin storing its level
+pub(crate) struct MockPin<'a>(pub(crate) &'a Cell<bool>);
+
+impl digital::ErrorType for MockPin<'_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set(true);
+        Ok(())
+    }
+}
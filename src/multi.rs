@@ -0,0 +1,122 @@
+//! Several SSD1327 panels tiled side by side as a single wider display
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::OriginDimensions,
+    geometry::Size,
+    pixelcolor::Gray4,
+    pixelcolor::GrayColor,
+    Pixel,
+};
+use embedded_hal::i2c::I2c;
+
+use crate::{FlushError, SSD1327I2C};
+
+/// Error returned when two panels of a `MultiDisplay` use the same slave address
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DuplicateAddress(pub u8);
+
+/// `P` SSD1327 panels tiled from left to right, drawn as a single display
+///
+/// Each panel keeps its own framebuffer, pixels are routed to the panel under their x coordinate.
+/// The panels are usually on the same I2C bus, shared with a crate like `embedded-hal-bus`.
+pub struct MultiDisplay<I2C, const N: usize, const P: usize>
+where
+    I2C: I2c,
+{
+    panels: [SSD1327I2C<I2C, N>; P],
+}
+
+impl <I2C, const N: usize, const P: usize> MultiDisplay<I2C, N, P>
+where
+    I2C: I2c,
+{
+    /// Tile the given panels from left to right
+    ///
+    /// Panels sharing a slave address would receive each other's commands and data,
+    /// so the first address used twice is returned as an error.
+    pub fn new(panels: [SSD1327I2C<I2C, N>; P]) -> Result<Self, DuplicateAddress> {
+        for (index, panel) in panels.iter().enumerate() {
+            let address = panel.slave_address();
+            if panels[..index].iter().any(|other| other.slave_address() == address) {
+                return Err(DuplicateAddress(address));
+            }
+        }
+        Ok(MultiDisplay { panels })
+    }
+
+    /// Panels, from left to right
+    pub fn panels(&self) -> &[SSD1327I2C<I2C, N>; P] {
+        &self.panels
+    }
+
+    /// Mutable panels, from left to right, to initialize or configure them
+    pub fn panels_mut(&mut self) -> &mut [SSD1327I2C<I2C, N>; P] {
+        &mut self.panels
+    }
+
+    /// Give the panels back
+    pub fn release(self) -> [SSD1327I2C<I2C, N>; P] {
+        self.panels
+    }
+
+    /// Initialize every panel, stops at the first command that fails
+    pub fn init(&mut self) -> Result<(), I2C::Error> {
+        self.panels.iter_mut().try_for_each(|panel| panel.init())
+    }
+
+    /// Update every panel with the area of its framebuffer modified since the last flush, see `SSD1327::flush`
+    pub fn flush(&mut self) -> Result<(), I2C::Error> {
+        self.panels.iter_mut().try_for_each(|panel| panel.flush())
+    }
+
+    /// Update every panel with its whole framebuffer, see `SSD1327::flush_all`
+    pub fn flush_all(&mut self) -> Result<(), FlushError<I2C::Error>> {
+        self.panels.iter_mut().try_for_each(|panel| panel.flush_all())
+    }
+}
+
+impl <I2C, const N: usize, const P: usize> DrawTarget for MultiDisplay<I2C, N, P>
+where
+    I2C: I2c,
+{
+    type Color = Gray4;
+
+    type Error = I2C::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(coord, color) in pixels.into_iter() {
+            let Ok((mut x, y)) = <(u32, u32)>::try_from(coord) else {
+                continue;
+            };
+            // Find the panel under x, and x relative to it
+            for panel in self.panels.iter_mut() {
+                let Size { width, height } = panel.size();
+                if x < width {
+                    if y < height {
+                        // Gamma corrected like drawing on the panel alone
+                        panel.set_pixel(x as u8, y as u8, color.luma());
+                    }
+                    break;
+                }
+                x -= width;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl <I2C, const N: usize, const P: usize> OriginDimensions for MultiDisplay<I2C, N, P>
+where
+    I2C: I2c,
+{
+    /// Sum of the panel widths, and height of the tallest panel
+    fn size(&self) -> Size {
+        self.panels.iter().map(|panel| panel.size()).fold(Size::zero(), |total, size| {
+            Size::new(total.width + size.width, total.height.max(size.height))
+        })
+    }
+}
@@ -0,0 +1,289 @@
+//! Minimal 5x7 font text output through `core::fmt::Write`, for quick debug printing
+
+use core::fmt;
+
+use heapless::String;
+
+use crate::interface::Interface;
+use crate::SSD1327;
+
+/// Width of a character cell in pixels, 5 pixels wide glyphs and 1 pixel of spacing
+pub const CHAR_WIDTH: u8 = 6;
+/// Height of a character cell in pixels, 7 pixels high glyphs and 1 pixel of spacing
+pub const CHAR_HEIGHT: u8 = 8;
+/// Gray value of the text, the rest of the character cell is cleared
+const TEXT_GRAY: u8 = 0x0F;
+
+/// Printable ASCII glyphs from ' ' to '~', 5 columns per glyph with the top row in the lowest bit
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x56, 0x20, 0x50], // '&'
+    [0x00, 0x08, 0x07, 0x03, 0x00], // '''
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // ')'
+    [0x2A, 0x1C, 0x7F, 0x1C, 0x2A], // '*'
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // '0'
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // '@'
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // 'A'
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // 'D'
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // 'F'
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // 'G'
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // 'H'
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // 'J'
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // 'M'
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // 'N'
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // 'O'
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // 'Q'
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // 'T'
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // 'U'
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // 'V'
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x07, 0x08, 0x70, 0x08, 0x07], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\'
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // 'f'
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // 'g'
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // 'j'
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // 'k'
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // 'l'
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // 'q'
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // 't'
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // 'u'
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // 'v'
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // 'y'
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x10, 0x08, 0x08, 0x10, 0x08], // '~'
+];
+
+/// Glyph of the given character, '?' for characters outside of printable ASCII
+fn glyph(c: char) -> &'static [u8; 5] {
+    match c {
+        ' '..='~' => &FONT[c as usize - ' ' as usize],
+        _ => &FONT['?' as usize - ' ' as usize],
+    }
+}
+
+impl <DI, const N: usize, RST, FB> SSD1327<DI, N, RST, FB>
+where
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Move the text cursor to (x, y), in pixels, the top left corner of the next character
+    pub fn set_cursor(&mut self, x: u8, y: u8) {
+        self.cursor = (x, y);
+    }
+
+    /// Current text cursor position, in pixels
+    pub fn cursor(&self) -> (u8, u8) {
+        self.cursor
+    }
+
+    /// Draw a character at the cursor and advance it, wrapping at the right edge and scrolling at the bottom
+    fn write_char_at_cursor(&mut self, c: char) {
+        let (width, height) = self.rotated_dimensions();
+        match c {
+            '\n' => self.new_line(height),
+            '\r' => self.cursor.0 = 0,
+            _ => {
+                if self.cursor.0 as u16 + CHAR_WIDTH as u16 > width as u16 {
+                    self.new_line(height);
+                }
+                let (x, y) = self.cursor;
+                let columns = glyph(c);
+                for dx in 0..CHAR_WIDTH {
+                    // The last column is the spacing
+                    let column = columns.get(dx as usize).copied().unwrap_or(0);
+                    for dy in 0..CHAR_HEIGHT {
+                        let gray = if column & (1 << dy) != 0 { TEXT_GRAY } else { 0 };
+                        self.set_pixel(x.saturating_add(dx), y.saturating_add(dy), gray);
+                    }
+                }
+                self.cursor.0 = x.saturating_add(CHAR_WIDTH);
+            }
+        }
+    }
+
+    /// Move the cursor to the start of the next line, scrolling the framebuffer up when there is no room left
+    fn new_line(&mut self, height: u8) {
+        self.cursor.0 = 0;
+        if self.cursor.1 as u16 + 2 * CHAR_HEIGHT as u16 <= height as u16 {
+            self.cursor.1 += CHAR_HEIGHT;
+        } else {
+            self.scroll_text_up();
+        }
+    }
+
+    /// Move every pixel up by one line of text and clear the bottom line
+    fn scroll_text_up(&mut self) {
+        let (width, height) = self.rotated_dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                let gray = match y.checked_add(CHAR_HEIGHT) {
+                    Some(below) => self.get_pixel(x, below).unwrap_or(0),
+                    None => 0,
+                };
+                self.set_pixel(x, y, gray);
+            }
+        }
+    }
+}
+
+impl <DI, const N: usize, RST, FB> fmt::Write for SSD1327<DI, N, RST, FB>
+where
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Draw the text in the framebuffer at the cursor, the display is updated on the next flush
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.write_char_at_cursor(c);
+        }
+        Ok(())
+    }
+}
+
+/// Status line at the bottom of the display, scrolling text too long for the width like a marquee
+///
+/// Holds up to `N` bytes of text. The text is drawn in the framebuffer by `draw` and `tick`,
+/// flushing the display stays up to the caller.
+pub struct StatusLine<const N: usize> {
+    text: String<N>,
+    offset: usize,
+}
+
+impl<const N: usize> StatusLine<N> {
+    /// Empty status line
+    pub fn new() -> Self {
+        StatusLine { text: String::new(), offset: 0 }
+    }
+
+    /// Replace the text and scroll back to its start, text beyond `N` bytes is dropped
+    pub fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        for c in text.chars() {
+            if self.text.push(c).is_err() {
+                break;
+            }
+        }
+        self.offset = 0;
+    }
+
+    /// Current text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Scroll offset in pixels, from the start of the text
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Scroll the text by one pixel to the left, then draw it
+    ///
+    /// Text fitting the width doesn't scroll. Otherwise the text is followed by a space before repeating,
+    /// and the offset wraps back to 0 once the whole text and space went by.
+    pub fn tick<DI, const M: usize, RST, FB>(&mut self, display: &mut SSD1327<DI, M, RST, FB>)
+    where
+        DI: Interface,
+        FB: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let (width, _) = display.rotated_dimensions();
+        let text_width = self.text.chars().count() * CHAR_WIDTH as usize;
+        if text_width > width as usize {
+            self.offset = (self.offset + 1) % (text_width + CHAR_WIDTH as usize);
+        } else {
+            self.offset = 0;
+        }
+        self.draw(display);
+    }
+
+    /// Draw the text at the current offset on the bottom `CHAR_HEIGHT` rows of the display
+    pub fn draw<DI, const M: usize, RST, FB>(&self, display: &mut SSD1327<DI, M, RST, FB>)
+    where
+        DI: Interface,
+        FB: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let (width, height) = display.rotated_dimensions();
+        let top = height.saturating_sub(CHAR_HEIGHT);
+        let chars = self.text.chars().count();
+        // The text and the space following it when scrolling
+        let cycle = (chars + 1) * CHAR_WIDTH as usize;
+        for x in 0..width {
+            let position = (self.offset + x as usize) % cycle;
+            let index = position / CHAR_WIDTH as usize;
+            let column = match self.text.chars().nth(index) {
+                // The last column of a cell is the spacing
+                Some(c) => glyph(c).get(position % CHAR_WIDTH as usize).copied().unwrap_or(0),
+                None => 0,
+            };
+            for dy in 0..CHAR_HEIGHT {
+                let gray = if column & (1 << dy) != 0 { TEXT_GRAY } else { 0 };
+                display.set_pixel(x, top.saturating_add(dy), gray);
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for StatusLine<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
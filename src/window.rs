@@ -0,0 +1,98 @@
+//! Sub-window of the SSD1327 drawn with local coordinates, for widgets owning a part of the display
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::Dimensions,
+    geometry::OriginDimensions,
+    geometry::Point,
+    geometry::Size,
+    pixelcolor::Gray4,
+    primitives::Rectangle,
+    Pixel,
+};
+
+use crate::interface::Interface;
+use crate::SSD1327;
+
+/// Area of the display drawn with (0, 0) at its top left corner, pixels outside of it are ignored
+///
+/// Draws straight in the framebuffer of the display, see `SSD1327::window`.
+pub struct Window<'a, DI, const N: usize, RST, FB>
+where
+    DI: Interface,
+{
+    display: &'a mut SSD1327<DI, N, RST, FB>,
+    area: Rectangle,
+}
+
+impl<'a, DI, const N: usize, RST, FB> Window<'a, DI, N, RST, FB>
+where
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Window over the given area of the display, clipped to the display
+    pub(crate) fn new(display: &'a mut SSD1327<DI, N, RST, FB>, area: Rectangle) -> Self {
+        let area = area.intersection(&display.bounding_box());
+        Window { display, area }
+    }
+
+    /// Area of the display covered by the window, in display coordinates
+    pub fn area(&self) -> Rectangle {
+        self.area
+    }
+
+    /// Update the display with the area of the window only
+    ///
+    /// The area is not removed from the area sent by `SSD1327::flush`.
+    pub fn flush(&mut self) -> Result<(), DI::Error> {
+        let Some(bottom_right) = self.area.bottom_right() else {
+            return Ok(());
+        };
+        let corners = [self.area.top_left, bottom_right].map(|corner| {
+            // Both corners are inside the display once clipped
+            self.display.framebuffer_coordinates(corner.x as u8, corner.y as u8).unwrap_or((0, 0))
+        });
+        let (min_x, max_x) = (corners[0].0.min(corners[1].0), corners[0].0.max(corners[1].0));
+        let (min_y, max_y) = (corners[0].1.min(corners[1].1), corners[0].1.max(corners[1].1));
+        self.display.flush_region(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
+    }
+}
+
+impl<DI, const N: usize, RST, FB> DrawTarget for Window<'_, DI, N, RST, FB>
+where
+    DI: Interface,
+    FB: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = Gray4;
+
+    type Error = DI::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let area = self.area;
+        let local = Rectangle::new(Point::zero(), area.size);
+        self.display.draw_iter(
+            pixels
+                .into_iter()
+                .filter(|Pixel(point, _)| local.contains(*point))
+                .map(|Pixel(point, color)| Pixel(point + area.top_left, color)),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let local = Rectangle::new(Point::zero(), self.area.size);
+        let area = area.intersection(&local);
+        self.display.fill_solid(&Rectangle::new(area.top_left + self.area.top_left, area.size), color)
+    }
+}
+
+impl<DI, const N: usize, RST, FB> OriginDimensions for Window<'_, DI, N, RST, FB>
+where
+    DI: Interface,
+{
+    fn size(&self) -> Size {
+        self.area.size
+    }
+}